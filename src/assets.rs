@@ -0,0 +1,359 @@
+use crate::effects;
+use crate::particle_system::ParticlePreset;
+use crate::sound_collection::Sound;
+use ggez::{audio, graphics, Context};
+use gwg as ggez;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+
+pub const MANIFEST_FILE: &str = "assets.txt";
+const RESOURCES_DIRECTORY: &str = "resources";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssetKind {
+    Font,
+    Image,
+    Data,
+    Sound,
+    Music,
+    Particles,
+}
+
+impl AssetKind {
+    pub fn from_name(name: &str) -> Option<AssetKind> {
+        match name {
+            "font" => Some(AssetKind::Font),
+            "image" => Some(AssetKind::Image),
+            "data" => Some(AssetKind::Data),
+            "sound" => Some(AssetKind::Sound),
+            "music" => Some(AssetKind::Music),
+            "particles" => Some(AssetKind::Particles),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AssetKind::Font => "font",
+            AssetKind::Image => "image",
+            AssetKind::Data => "data",
+            AssetKind::Sound => "sound",
+            AssetKind::Music => "music",
+            AssetKind::Particles => "particles",
+        }
+    }
+}
+
+pub struct AssetEntry {
+    pub kind: AssetKind,
+    pub role: String, // what the game uses it for, sounds and particles use the name of the effect
+    pub path: String, // relative to resources, always without a leading slash
+    pub is_optional: bool,
+}
+
+// Every file the game loads and what it's used for, read from resources/assets.txt
+// Swapping the paths is enough for a reskin or a new sound pack
+//
+// # kind | role | path | optional
+// font | ui | kenny_fontpackage/Fonts/Kenney Mini.ttf
+// sound | player_walk | sounds/player_walk.wav
+// music | background | sounds/music.wav | optional
+// particles | blood | particles/blood.txt
+pub struct Manifest {
+    pub entries: Vec<AssetEntry>,
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    NotFound(String),
+    InvalidLine(usize, String),
+    Missing(Vec<String>), // every problem found, not just the first
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::NotFound(e) => write!(f, "{}: can't open ({})", MANIFEST_FILE, e),
+            AssetError::InvalidLine(line, text) => write!(
+                f,
+                "{} line {}: expected 'kind | role | path', got '{}'",
+                MANIFEST_FILE, line, text
+            ),
+            AssetError::Missing(problems) => {
+                write!(f, "{}: {} assets missing or unknown", MANIFEST_FILE, problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn parse_manifest(text: &str) -> Result<Manifest, AssetError> {
+    let mut entries = vec![];
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid_line = || AssetError::InvalidLine(line_index + 1, line.to_string());
+        let parts: Vec<&str> = line.split('|').map(str::trim).collect();
+        let is_optional = match parts.get(3) {
+            None => false,
+            Some(&"optional") => true,
+            Some(_) => return Err(invalid_line()),
+        };
+        if parts.len() < 3 || parts.len() > 4 || parts[1].is_empty() || parts[2].is_empty() {
+            return Err(invalid_line());
+        }
+        entries.push(AssetEntry {
+            kind: AssetKind::from_name(parts[0]).ok_or_else(invalid_line)?,
+            role: parts[1].to_string(),
+            path: parts[2].trim_start_matches('/').to_string(),
+            is_optional,
+        });
+    }
+    Ok(Manifest { entries })
+}
+
+// The filesystem wants text files from the root of the resources, images and sounds take the path as is
+fn read_text(ctx: &mut Context, path: &str) -> Result<String, String> {
+    let mut buffer = String::new();
+    ggez::filesystem::open(ctx, &format!("/{}", path))
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|mut file| file.read_to_string(&mut buffer).map_err(|e| e.to_string()))?;
+    Ok(buffer)
+}
+
+fn load_preset(
+    entry: &AssetEntry,
+    text: Result<String, String>,
+    presets: &mut HashMap<String, ParticlePreset>,
+    problems: &mut Vec<String>,
+) {
+    let problem = match text.map(|text| text.parse::<ParticlePreset>()) {
+        Ok(Ok(preset)) => {
+            presets.insert(entry.role.clone(), preset);
+            return;
+        }
+        Ok(Err(e)) => format!("{} {}", entry.path, e),
+        Err(_) => format!("can't open {}", entry.path),
+    };
+    problems.push(format!("particles {}: {}", entry.role, problem));
+}
+
+// Desktop only, reads the particle presets from the resources folder instead of the packed
+// resources, so effects can be tuned while the game runs
+pub fn reload_particle_presets() -> Result<HashMap<String, ParticlePreset>, AssetError> {
+    let read = |path: &str| {
+        let path = format!("{}/{}", RESOURCES_DIRECTORY, path);
+        std::fs::read_to_string(path).map_err(|e| e.to_string())
+    };
+    let manifest = parse_manifest(&read(MANIFEST_FILE).map_err(AssetError::NotFound)?)?;
+    let mut presets = HashMap::new();
+    let mut problems = vec![];
+    for entry in manifest.entries.iter() {
+        if entry.kind == AssetKind::Particles {
+            load_preset(entry, read(&entry.path), &mut presets, &mut problems);
+        }
+    }
+    match problems.is_empty() {
+        true => Ok(presets),
+        false => Err(AssetError::Missing(problems)),
+    }
+}
+
+// Everything in the manifest, loaded
+pub struct Assets {
+    pub font: graphics::Font,
+    pub atlas_image: graphics::Image,
+    pub atlas_data: String,
+    pub sounds: HashMap<Sound, audio::Source>,
+    pub music: Option<audio::Source>,
+    pub particle_presets: HashMap<String, ParticlePreset>,
+}
+
+impl Assets {
+    // Tries every file before failing, so all missing files are reported at once
+    pub fn load(ctx: &mut Context) -> Result<Assets, AssetError> {
+        let manifest = read_text(ctx, MANIFEST_FILE).map_err(AssetError::NotFound)?;
+        let manifest = parse_manifest(&manifest)?;
+
+        let mut problems = vec![];
+        let mut font = None;
+        let mut atlas_image = None;
+        let mut atlas_data = None;
+        let mut sounds = HashMap::new();
+        let mut music = None;
+        let mut particle_presets = HashMap::new();
+        for entry in manifest.entries.iter() {
+            let path = entry.path.as_str();
+            let result = match (entry.kind, entry.role.as_str()) {
+                (AssetKind::Font, "ui") => graphics::Font::new(ctx, path).map(|f| font = Some(f)),
+                (AssetKind::Image, "atlas") => {
+                    graphics::Image::new(ctx, path).map(|image| atlas_image = Some(image))
+                }
+                (AssetKind::Data, "atlas") => {
+                    match read_text(ctx, path) {
+                        Ok(text) => atlas_data = Some(text),
+                        Err(_) => problems.push(format!("data atlas: can't open {}", path)),
+                    }
+                    continue;
+                }
+                (AssetKind::Sound, role) => match Sound::from_name(role) {
+                    Some(sound) => audio::Source::new(ctx, path).map(|source| {
+                        sounds.insert(sound, source);
+                    }),
+                    None => {
+                        problems.push(format!("sound {}: no such sound", role));
+                        continue;
+                    }
+                },
+                (AssetKind::Particles, _) => {
+                    let text = read_text(ctx, path);
+                    load_preset(entry, text, &mut particle_presets, &mut problems);
+                    continue;
+                }
+                (AssetKind::Music, "background") => {
+                    audio::Source::new(ctx, path).map(|source| music = Some(source))
+                }
+                (kind, role) => {
+                    problems.push(format!("{} {}: unknown role", kind.name(), role));
+                    continue;
+                }
+            };
+            if result.is_err() {
+                let problem = format!("{} {}: can't open {}", entry.kind.name(), entry.role, path);
+                match entry.is_optional {
+                    true => eprintln!("{}, skipped", problem),
+                    false => problems.push(problem),
+                }
+            }
+        }
+
+        // Roles the game can't do without, listed or not
+        let is_listed = |kind: AssetKind, role: &str| {
+            manifest
+                .entries
+                .iter()
+                .any(|entry| entry.kind == kind && entry.role == role)
+        };
+        let mut required = vec![
+            (AssetKind::Font, "ui"),
+            (AssetKind::Image, "atlas"),
+            (AssetKind::Data, "atlas"),
+        ];
+        required.extend(
+            Sound::ALL
+                .iter()
+                .map(|sound| (AssetKind::Sound, sound.name())),
+        );
+        required.extend(
+            effects::EFFECT_NAMES
+                .iter()
+                .map(|name| (AssetKind::Particles, *name)),
+        );
+        for (kind, role) in required {
+            if !is_listed(kind, role) {
+                problems.push(format!("{} {}: not in the manifest", kind.name(), role));
+            }
+        }
+
+        match (font, atlas_image, atlas_data) {
+            (Some(font), Some(atlas_image), Some(atlas_data)) if problems.is_empty() => {
+                Ok(Assets {
+                    font,
+                    atlas_image,
+                    atlas_data,
+                    sounds,
+                    music,
+                    particle_presets,
+                })
+            }
+            _ => Err(AssetError::Missing(problems)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_line(text: &str) -> usize {
+        match parse_manifest(text) {
+            Err(AssetError::InvalidLine(line, _)) => line,
+            Err(e) => panic!("{:?}: {}", text, e),
+            Ok(_) => panic!("expected an error for {:?}", text),
+        }
+    }
+
+    #[test]
+    fn entries() {
+        let manifest = parse_manifest(
+            "# comment\n\
+             \n\
+             font | ui | kenny_fontpackage/Fonts/Kenney Mini.ttf\n\
+             music | background | sounds/music.wav | optional\n\
+             particles | blood | /particles/blood.txt\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.entries.len(), 3);
+        let font = &manifest.entries[0];
+        assert_eq!(font.kind, AssetKind::Font);
+        assert_eq!(font.role, "ui");
+        assert_eq!(font.path, "kenny_fontpackage/Fonts/Kenney Mini.ttf");
+        assert!(!font.is_optional);
+        assert_eq!(manifest.entries[1].kind, AssetKind::Music);
+        assert!(manifest.entries[1].is_optional);
+        // One path form, whatever the line was written with
+        assert_eq!(manifest.entries[2].path, "particles/blood.txt");
+    }
+
+    #[test]
+    fn invalid_lines() {
+        assert_eq!(invalid_line("video | intro | intro.mp4\n"), 1);
+        assert_eq!(invalid_line("\nsound | player_walk\n"), 2);
+        assert_eq!(invalid_line("sound | | sounds/a.wav\n"), 1);
+        assert_eq!(invalid_line("sound | a | sounds/a.wav | maybe\n"), 1);
+        assert_eq!(invalid_line("sound | a | sounds/a.wav | optional | x\n"), 1);
+    }
+
+    #[test]
+    fn kind_names_roundtrip() {
+        let kinds = [
+            AssetKind::Font,
+            AssetKind::Image,
+            AssetKind::Data,
+            AssetKind::Sound,
+            AssetKind::Music,
+            AssetKind::Particles,
+        ];
+        for kind in kinds.iter() {
+            assert_eq!(AssetKind::from_name(kind.name()), Some(*kind));
+        }
+    }
+
+    #[test]
+    fn shipped_manifest() {
+        let manifest = parse_manifest(include_str!("../resources/assets.txt")).unwrap();
+        for sound in Sound::ALL.iter() {
+            assert!(
+                manifest
+                    .entries
+                    .iter()
+                    .any(|entry| entry.kind == AssetKind::Sound && entry.role == sound.name()),
+                "no sound for {}",
+                sound.name()
+            );
+        }
+        // Every file listed is there
+        let resources = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/");
+        for entry in manifest.entries.iter() {
+            assert!(!entry.path.starts_with('/'), "{} starts with a slash", entry.path);
+            let path = format!("{}{}", resources, entry.path);
+            assert!(std::path::Path::new(&path).exists(), "{} is missing", path);
+        }
+    }
+}
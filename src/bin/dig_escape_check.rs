@@ -0,0 +1,131 @@
+// Checks map files without starting the game
+//
+// dig_escape_check                      checks every map listed in resources/maps/campaign.txt,
+//                                       and that each par can be reached by the shortest solution
+// dig_escape_check maps/a.txt maps/b.txt checks the given map files
+// --max-states <n>                       search limit before the solver gives up
+//
+// Exits with 1 if any map can't be read or can't be won
+use dig_escape::campaign;
+use dig_escape::map;
+use dig_escape::solver::{self, SolveResult};
+use dig_escape::states::game_state::GameState;
+use std::process;
+
+// Found from the repository, so the check runs from any directory
+const RESOURCES_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/resources");
+
+enum CheckResult {
+    Solved {
+        moves: usize,
+        counted_moves: u32,
+        skeletons_released: usize,
+    },
+    Failed(String),
+}
+
+fn check_map(path: &str, par: Option<u32>, max_states: usize) -> CheckResult {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => return CheckResult::Failed(format!("can't read map: {}", e)),
+    };
+    let map_data = match map::parse_map(&text) {
+        Ok(map_data) => map_data,
+        Err(e) => return CheckResult::Failed(e.to_string()),
+    };
+    let mut game_state = GameState::new();
+    map::build_map(&mut game_state, &map_data);
+
+    match solver::solve(&game_state, max_states) {
+        SolveResult::Solved(moves) => {
+            let end_state = solver::play_moves(&game_state, &moves);
+            // The par comes from the move counter of the shortest solution, it can't be lower
+            match par {
+                Some(par) if par < end_state.move_count => {
+                    return CheckResult::Failed(format!(
+                        "par {} is below the {} moves of the shortest solution",
+                        par, end_state.move_count
+                    ));
+                }
+                _ => {}
+            }
+            CheckResult::Solved {
+                moves: moves.len(),
+                // What the move counter shows, a good par for the level
+                counted_moves: end_state.move_count,
+                skeletons_released: end_state
+                    .skeleton_blocks
+                    .iter()
+                    .filter(|block| block.buried.is_released)
+                    .count(),
+            }
+        }
+        SolveResult::Unsolvable => CheckResult::Failed("unsolvable".to_string()),
+        SolveResult::GaveUp => CheckResult::Failed(format!(
+            "no solution within {} states, try a higher --max-states",
+            max_states
+        )),
+    }
+}
+
+fn main() {
+    let mut max_states = solver::DEFAULT_MAX_STATES;
+    let mut paths: Vec<(String, Option<u32>)> = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-states" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => max_states = n,
+                None => {
+                    eprintln!("--max-states needs a number");
+                    process::exit(2);
+                }
+            },
+            _ => paths.push((arg, None)),
+        }
+    }
+    if paths.is_empty() {
+        let campaign_path = format!("{}{}", RESOURCES_DIRECTORY, campaign::CAMPAIGN_FILE);
+        let campaign = std::fs::read_to_string(&campaign_path)
+            .map_err(|e| format!("{}: {}", campaign_path, e))
+            .and_then(|text| campaign::parse_campaign(&text).map_err(|e| e.to_string()));
+        match campaign {
+            Ok(campaign) => {
+                for level in campaign.levels.iter() {
+                    let path = format!("{}{}", RESOURCES_DIRECTORY, level.map_name);
+                    paths.push((path, level.par));
+                }
+            }
+            Err(e) => {
+                println!("FAILED {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut failed = 0;
+    for (path, par) in paths.iter() {
+        match check_map(path, *par, max_states) {
+            CheckResult::Solved {
+                moves,
+                counted_moves,
+                skeletons_released,
+            } => {
+                let par = par.map_or("no par".to_string(), |par| format!("par {}", par));
+                println!(
+                    "ok     {}: shortest solution {} moves ({} counted, {}), {} skeletons released",
+                    path, moves, counted_moves, par, skeletons_released
+                );
+            }
+            CheckResult::Failed(reason) => {
+                failed += 1;
+                println!("FAILED {}: {}", path, reason);
+            }
+        }
+    }
+
+    println!("{} maps checked, {} failed", paths.len(), failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
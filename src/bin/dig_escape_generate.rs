@@ -0,0 +1,110 @@
+// Generates random maps that are checked to be winnable
+//
+// dig_escape_generate                         prints one medium map
+// --difficulty <easy|medium|hard>             target length of the shortest solution
+// --seed <n>                                  same seed, same map
+// --count <n>                                 maps to generate, seeds counting up from --seed
+// --width <n> --height <n>                    map size, 10x8 by default
+// --out <directory>                           writes map_generated_<seed>.txt files instead of printing
+//
+// Exits with 1 if no map fitting the difficulty was found, 2 for invalid arguments
+use dig_escape::generator::{self, Difficulty, GeneratorError, GeneratorSettings};
+use dig_escape::map;
+use dig_escape::solver;
+use dig_escape::states::game_state::GameState;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn parse_or_exit<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.and_then(|value| value.parse().ok()) {
+        Some(value) => value,
+        None => {
+            eprintln!("{} needs a number", flag);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut difficulty = Difficulty::Medium;
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let mut count: u64 = 1;
+    let mut width = None;
+    let mut height = None;
+    let mut out_directory = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" => match args.next().as_deref().and_then(Difficulty::from_name) {
+                Some(value) => difficulty = value,
+                None => {
+                    eprintln!("--difficulty needs easy, medium or hard");
+                    process::exit(2);
+                }
+            },
+            "--seed" => seed = parse_or_exit("--seed", args.next()),
+            "--count" => count = parse_or_exit("--count", args.next()),
+            "--width" => width = Some(parse_or_exit("--width", args.next())),
+            "--height" => height = Some(parse_or_exit("--height", args.next())),
+            "--out" => out_directory = args.next(),
+            _ => {
+                eprintln!("unknown argument {}", arg);
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut settings = GeneratorSettings::new(difficulty);
+    settings.width = width.unwrap_or(settings.width);
+    settings.height = height.unwrap_or(settings.height);
+    if let Err(e) = settings.check() {
+        eprintln!("{}", e);
+        process::exit(2);
+    }
+    for seed in (0..count).map(|i| seed.wrapping_add(i)) {
+        let generated = match generator::generate(&settings, seed) {
+            Ok(generated) => generated,
+            Err(GeneratorError::NotFound(attempts)) => {
+                println!(
+                    "FAILED seed {}: no {} map after {} attempts",
+                    seed,
+                    difficulty.name(),
+                    attempts
+                );
+                process::exit(1);
+            }
+            Err(e) => {
+                println!("FAILED seed {}: {}", seed, e);
+                process::exit(1);
+            }
+        };
+        let mut game_state = GameState::new();
+        map::build_map(&mut game_state, &generated.map_data);
+        let counted_moves = solver::count_moves(&game_state, &generated.solution);
+        let text = map::map_to_string(&generated.map_data);
+        let summary = format!(
+            "seed {}: {} map, shortest solution {} moves ({} counted), {} attempts",
+            seed,
+            difficulty.name(),
+            generated.solution.len(),
+            counted_moves,
+            generated.attempts
+        );
+        match &out_directory {
+            Some(directory) => {
+                let path = format!("{}/map_generated_{}.txt", directory, seed);
+                let result = std::fs::create_dir_all(directory)
+                    .and_then(|_| std::fs::write(&path, text));
+                if let Err(e) = result {
+                    println!("FAILED {}: {}", path, e);
+                    process::exit(1);
+                }
+                println!("ok     {} {}", path, summary);
+            }
+            None => println!("{}\n{}", summary, text),
+        }
+    }
+}
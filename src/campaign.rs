@@ -0,0 +1,123 @@
+use ggez::Context;
+use gwg as ggez;
+
+use std::fmt;
+use std::io::Read;
+
+pub const CAMPAIGN_FILE: &str = "/maps/campaign.txt";
+const MAPS_DIRECTORY: &str = "/maps";
+
+pub struct Level {
+    pub map_name: String, // resource path, e.g. /maps/map_first.txt
+    pub title: String,
+    pub par: Option<u32>, // moves needed for 3 stars
+}
+
+// The levels in the order they are played, read from resources/maps/campaign.txt
+//
+// # comment
+// map_first.txt | Dig Out | 5
+// map_1skeleton.txt | Something Buried
+//
+// The par (last column) is optional, levels without one don't get stars
+pub struct Campaign {
+    pub levels: Vec<Level>,
+}
+
+#[derive(Debug)]
+pub enum CampaignError {
+    NotFound(String),
+    InvalidLine(usize, String),
+    Empty,
+}
+
+impl fmt::Display for CampaignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CampaignError::NotFound(e) => write!(f, "{}: can't open ({})", CAMPAIGN_FILE, e),
+            CampaignError::InvalidLine(line, text) => write!(
+                f,
+                "{} line {}: expected 'map file | title | par', got '{}'",
+                CAMPAIGN_FILE, line, text
+            ),
+            CampaignError::Empty => write!(f, "{}: no levels", CAMPAIGN_FILE),
+        }
+    }
+}
+
+impl Campaign {
+    pub fn empty() -> Self {
+        Campaign { levels: vec![] }
+    }
+
+    pub fn load(ctx: &mut Context) -> Result<Campaign, CampaignError> {
+        let mut buffer = String::new();
+        ggez::filesystem::open(ctx, CAMPAIGN_FILE)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|mut file| {
+                file.read_to_string(&mut buffer)
+                    .map_err(|e| e.to_string())
+            })
+            .map_err(CampaignError::NotFound)?;
+        parse_campaign(&buffer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    pub fn map_name(&self, index: usize) -> Option<&str> {
+        self.levels.get(index).map(|level| level.map_name.as_str())
+    }
+
+    pub fn title(&self, index: usize) -> Option<&str> {
+        self.levels.get(index).map(|level| level.title.as_str())
+    }
+
+    pub fn par(&self, index: usize) -> Option<u32> {
+        self.levels.get(index).and_then(|level| level.par)
+    }
+
+    pub fn find_map_index(&self, map_name: &str) -> Option<usize> {
+        self.levels
+            .iter()
+            .position(|level| level.map_name == map_name)
+    }
+}
+
+pub fn parse_campaign(text: &str) -> Result<Campaign, CampaignError> {
+    let mut levels = vec![];
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid_line = || CampaignError::InvalidLine(line_index + 1, line.to_string());
+        let mut parts = line.split('|').map(str::trim);
+        let file = parts.next().unwrap_or("");
+        let title = parts.next().ok_or_else(invalid_line)?;
+        if file.is_empty() || title.is_empty() {
+            return Err(invalid_line());
+        }
+        let par = match parts.next() {
+            Some(par) => Some(par.parse::<u32>().map_err(|_| invalid_line())?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(invalid_line());
+        }
+        levels.push(Level {
+            map_name: format!("{}/{}", MAPS_DIRECTORY, file.trim_start_matches('/')),
+            title: title.to_string(),
+            par,
+        });
+    }
+    if levels.is_empty() {
+        return Err(CampaignError::Empty);
+    }
+    Ok(Campaign { levels })
+}
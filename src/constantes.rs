@@ -1,8 +1,9 @@
 use ggez::graphics::Color;
 use gwg as ggez;
 
-pub const GAME_BOUNDS_Y: i32 = 7;
-pub const GAME_BOUNDS_X: i32 = 9;
+pub const DEFAULT_MAP_WIDTH: i32 = 10; // maps set their own size, this is the empty game state
+pub const DEFAULT_MAP_HEIGHT: i32 = 8;
+pub const MAX_MAP_SIZE: i32 = 64; // biggest width or height a map can have
 pub const GAME_BOUNDS_PADDING: f32 = 5.0; // Warp clouds
 
 pub const MAX_CLOUDS: i32 = 8;
@@ -21,13 +22,30 @@ pub const CLEAR_COLOR: Color = Color::new(0.0, 0.0, 0.0, 1.0);
 pub const BACKGROUND_GAME: Color = Color::new(56.0 / 255.0, 82.0 / 255.0, 119.0 / 255.0, 1.0);
 pub const COLOR_BLINK: Color = Color::new(2.0, 2.0, 2.0, 1.0);
 pub const COLOR_BLOOD: Color = Color::new(171.0 / 255.0, 34.0 / 255.0, 44.0 / 255.0, 1.0);
+pub const COLOR_OVERLAY: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+pub const COLOR_MENU: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+pub const COLOR_MENU_SELECTED: Color = Color::new(1.0, 216.0 / 255.0, 0.0, 1.0);
+pub const COLOR_MENU_LOCKED: Color = Color::new(0.5, 0.5, 0.5, 1.0);
+pub const COLOR_MENU_COMPLETED: Color = Color::new(0.5, 1.0, 0.5, 1.0);
+pub const COLOR_STAR: Color = Color::new(1.0, 190.0 / 255.0, 40.0 / 255.0, 1.0);
+pub const COLOR_SPLIT_AHEAD: Color = Color::new(0.5, 1.0, 0.5, 1.0);
+pub const COLOR_SPLIT_BEHIND: Color = Color::new(1.0, 0.45, 0.45, 1.0);
+pub const COLOR_HINT: Color = Color::new(1.0, 216.0 / 255.0, 0.0, 1.0);
+pub const COLOR_EDITOR_GRID: Color = Color::new(1.0, 1.0, 1.0, 0.15);
+pub const COLOR_THUMBNAIL_LOCKED: Color = Color::new(0.3, 0.3, 0.3, 1.0);
 
 pub const TIME_BLINK: f32 = 0.4;
 pub const TIME_AUTO_STEP: f32 = 0.2;
 pub const TIME_VISUAL_LERP: f32 = 1.0 / 0.2 * 2.0;
+pub const TIME_REPLAY_STEP: f32 = 0.3;
 
 pub const GAME_SCALE: f32 = 5.0;
 
 pub const TOUCH_MIN_DELTA: f32 = 10.0;
+pub const TOUCH_UNDO_ZONE: f32 = 0.2; // procentage of screen width on each side, tap left to undo, right to redo
 pub const TEXT_PADDING_SIZE: f32 = 0.3; // fits all text inside screen with this padding in procentage
 pub const PI: f32 = std::f32::consts::PI;
+
+pub const REPLAY_DIRECTORY: &str = "replays";
+
+pub const EDITOR_MAP_PATH: &str = "resources/maps/map_custom.txt"; // add it to campaign.txt to play it
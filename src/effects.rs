@@ -0,0 +1,273 @@
+use crate::events::GameEvent;
+use crate::game_rng::GameRng;
+use crate::particle_system::{
+    AngleData, EmitShape, LineData, ParticlePreset, ParticleSystem, ParticleSystemCollection,
+    SpawnType, VelocityType,
+};
+use crate::sprite::SpriteCollection;
+use crate::states::game_state::GameState;
+use ggez::Context;
+use gwg as ggez;
+use nalgebra as na;
+use std::collections::HashMap;
+
+// Effects the game triggers, each one is a preset file listed in resources/assets.txt
+// Dug foilage triggers the effect named after its sprite group
+pub const EFFECT_NAMES: &[&str] = &[
+    "grass", "step", "blood", "land", "straw", "bush", "row_dust",
+];
+
+pub struct ParticleEffect {
+    pub preset: ParticlePreset,
+    pub system_ids: Vec<u32>, // one system, or one per sprite in the group the preset draws
+}
+
+// The particle systems used for gameplay effects, added to the collection at startup
+pub struct ParticleEffects {
+    pub effects: HashMap<String, ParticleEffect>,
+}
+
+// Particle position of the player sprite, offset in sprite pixels (16 per tile)
+fn player_particle_position(
+    game_state: &GameState,
+    screen_size: &na::Point2<f32>,
+    offset: na::Vector2<f32>,
+) -> na::Vector2<f32> {
+    let pos_player_visual = game_state.player.sprite.visual_position;
+    na::Vector2::new(
+        pos_player_visual.x / screen_size.x * 16.0,
+        pos_player_visual.y / screen_size.x * 16.0,
+    ) + offset
+}
+
+// Left and right end of the unbroken row of grass through a tile, in sprite pixels (16 per tile)
+// along the top of the row
+fn grass_row(
+    game_state: &GameState,
+    position: &na::Point2<i32>,
+) -> (na::Vector2<f32>, na::Vector2<f32>) {
+    let is_grass = |x: i32| game_state.grid.cell(&na::Point2::new(x, position.y)).grass;
+    let mut left = position.x;
+    while is_grass(left - 1) {
+        left -= 1;
+    }
+    let mut right = position.x;
+    while is_grass(right + 1) {
+        right += 1;
+    }
+    let y = position.y as f32 * 16.0;
+    (
+        na::Vector2::new(left as f32 * 16.0, y),
+        na::Vector2::new((right + 1) as f32 * 16.0, y),
+    )
+}
+
+impl ParticleEffects {
+    pub fn new(
+        ctx: &mut Context,
+        presets: &HashMap<String, ParticlePreset>,
+        sprite_collection: &SpriteCollection,
+        particle_collection: &mut ParticleSystemCollection,
+    ) -> Self {
+        let mut effects = HashMap::new();
+        for (name, preset) in presets.iter() {
+            let system_ids = match &preset.sprite {
+                Some(group) => sprite_collection
+                    .group(group)
+                    .iter()
+                    .map(|region_index| {
+                        let atlas = sprite_collection.atlas.clone();
+                        let mut particle_system = ParticleSystem::new(ctx, Some(atlas));
+                        particle_system.apply_preset(preset);
+                        particle_system.src = sprite_collection.regions[*region_index].src;
+                        particle_collection.add_system(particle_system)
+                    })
+                    .collect(),
+                None => {
+                    let mut particle_system = ParticleSystem::new(ctx, None);
+                    particle_system.apply_preset(preset);
+                    vec![particle_collection.add_system(particle_system)]
+                }
+            };
+            let effect = ParticleEffect {
+                preset: preset.clone(),
+                system_ids,
+            };
+            effects.insert(name.clone(), effect);
+        }
+        ParticleEffects { effects }
+    }
+
+    // New settings for the existing systems, the sprites they draw stay the same
+    pub fn reload(
+        &mut self,
+        presets: &HashMap<String, ParticlePreset>,
+        particle_collection: &mut ParticleSystemCollection,
+    ) {
+        for (name, effect) in self.effects.iter_mut() {
+            if let Some(preset) = presets.get(name) {
+                for system_id in effect.system_ids.iter() {
+                    if let Some(particle_system) = particle_collection.get_mut(*system_id) {
+                        particle_system.apply_preset(preset);
+                    }
+                }
+                effect.preset = preset.clone();
+            }
+        }
+    }
+
+    // Emits the preset amount at a position in sprite pixels (16 per tile)
+    // The variant picks the sprite like the sprite collection does, flipped mirrors the direction
+    pub fn trigger(
+        &self,
+        particle_collection: &mut ParticleSystemCollection,
+        name: &str,
+        variant: usize,
+        is_flipped: bool,
+        position: na::Vector2<f32>,
+        screen_size: &na::Point2<f32>,
+        rng: &mut GameRng,
+    ) {
+        let effect = match self.effects.get(name) {
+            Some(effect) if !effect.system_ids.is_empty() => effect,
+            _ => return,
+        };
+        let system_id = effect.system_ids[variant % effect.system_ids.len()];
+        let particle_system = match particle_collection.get_mut(system_id) {
+            Some(particle_system) => particle_system,
+            None => return,
+        };
+        particle_system.velocity_type = match (effect.preset.velocity_type, is_flipped) {
+            (VelocityType::Angle(a), true) => {
+                VelocityType::Angle(AngleData::new(-a.angle, a.max_delta))
+            }
+            (velocity_type, _) => velocity_type,
+        };
+        particle_system.scale = screen_size.x / 16.0;
+        particle_system.position = position;
+        particle_system.emit(effect.preset.amount, rng);
+    }
+
+    // Emits along the line between two points in sprite pixels, for presets with a line shape.
+    // The preset decides whether particles spawn along the line or at its ends
+    pub fn trigger_line(
+        &self,
+        particle_collection: &mut ParticleSystemCollection,
+        name: &str,
+        from: na::Vector2<f32>,
+        to: na::Vector2<f32>,
+        screen_size: &na::Point2<f32>,
+        rng: &mut GameRng,
+    ) {
+        let effect = match self.effects.get(name) {
+            Some(effect) => effect,
+            None => return,
+        };
+        let spawn_type = match effect.preset.emit_shape {
+            EmitShape::Line(line) => line.spawn_type,
+            _ => SpawnType::Volume,
+        };
+        for system_id in effect.system_ids.iter() {
+            if let Some(particle_system) = particle_collection.get_mut(*system_id) {
+                particle_system.emit_shape = EmitShape::Line(LineData {
+                    vector: to - from,
+                    spawn_type,
+                });
+            }
+        }
+        self.trigger(
+            particle_collection,
+            name,
+            0,
+            false,
+            (from + to) * 0.5,
+            screen_size,
+            rng,
+        );
+    }
+
+    // Particle listener, effects start where the player sprite is drawn
+    pub fn on_event(
+        &self,
+        event: &GameEvent,
+        particle_collection: &mut ParticleSystemCollection,
+        game_state: &GameState,
+        screen_size: &na::Point2<f32>,
+        rng: &mut GameRng,
+    ) {
+        let under_player =
+            player_particle_position(game_state, screen_size, na::Vector2::new(16.0 * 0.5, 16.0));
+        match event {
+            GameEvent::PlayerStepped { from, to } => {
+                // The preset throws dust behind a player walking left
+                let is_right_dir = to.x > from.x;
+                self.trigger(
+                    particle_collection,
+                    "step",
+                    0,
+                    is_right_dir,
+                    under_player,
+                    screen_size,
+                    rng,
+                );
+            }
+            GameEvent::PlayerDug { position, foilage } => {
+                let (left, right) = grass_row(game_state, position);
+                self.trigger_line(
+                    particle_collection,
+                    "row_dust",
+                    left,
+                    right,
+                    screen_size,
+                    rng,
+                );
+                self.trigger(
+                    particle_collection,
+                    "grass",
+                    0,
+                    false,
+                    under_player,
+                    screen_size,
+                    rng,
+                );
+                if let Some(texture) = foilage {
+                    let variant = texture.variant().unwrap_or(0);
+                    self.trigger(
+                        particle_collection,
+                        texture.name(),
+                        variant,
+                        false,
+                        under_player,
+                        screen_size,
+                        rng,
+                    );
+                }
+            }
+            GameEvent::PlayerKilled { .. } => {
+                let center = na::Vector2::new(16.0 * 0.5, 16.0 * 0.5);
+                let position = player_particle_position(game_state, screen_size, center);
+                self.trigger(
+                    particle_collection,
+                    "blood",
+                    0,
+                    false,
+                    position,
+                    screen_size,
+                    rng,
+                );
+            }
+            GameEvent::Landed { .. } => {
+                self.trigger(
+                    particle_collection,
+                    "land",
+                    0,
+                    false,
+                    under_player,
+                    screen_size,
+                    rng,
+                );
+            }
+            _ => {}
+        }
+    }
+}
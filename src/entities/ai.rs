@@ -1,9 +1,10 @@
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum AiState {
     Walk,
     Attack,
 }
 
+#[derive(Clone)]
 pub struct AiComponent {
     pub state: AiState,
     pub turn_taken: bool,
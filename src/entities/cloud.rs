@@ -1,15 +1,17 @@
 use crate::constantes;
+use crate::game_rng::GameRng;
 use crate::sprite;
 use crate::states::game_state;
 use game_state::GameState;
 use nalgebra as na;
-use sprite::{SpriteCollection, SpriteComponent};
+use sprite::{SpriteCollection, SpriteComponent, Texture};
 
 use ggez::graphics;
-use ggez::{rand, Context, GameResult};
+use ggez::{Context, GameResult};
 use graphics::DrawParam;
 use gwg as ggez;
 
+#[derive(Clone)]
 pub struct Cloud {
     pub sprite: SpriteComponent,
     pub position: na::Point2<f32>,
@@ -17,20 +19,20 @@ pub struct Cloud {
 }
 
 impl Cloud {
-    pub fn new() -> Self {
-        let speed = rand::gen_range(constantes::CLOUD_MIN_SPEED, constantes::CLOUD_MAX_SPEED);
-        let scale_x = rand::gen_range(1.0, constantes::CLOUD_MAX_SCALE);
-        let scale_y = rand::gen_range(1.0, constantes::CLOUD_MAX_SCALE);
+    pub fn new(map_size: &na::Point2<i32>, rng: &mut GameRng) -> Self {
+        let speed = rng.gen_range(constantes::CLOUD_MIN_SPEED, constantes::CLOUD_MAX_SPEED);
+        let scale_x = rng.gen_range(1.0, constantes::CLOUD_MAX_SCALE);
+        let scale_y = rng.gen_range(1.0, constantes::CLOUD_MAX_SCALE);
         let scale = na::Vector2::new(scale_x, scale_y);
-        let texture_index = rand::gen_range(18, 20 + 1);
-        let position_x = rand::gen_range(
+        let texture = Texture::Cloud(rng.gen_range(0, sprite::VARIANT_RANGE));
+        let position_x = rng.gen_range(
             -constantes::GAME_BOUNDS_PADDING,
-            constantes::GAME_BOUNDS_X as f32 + constantes::GAME_BOUNDS_PADDING,
+            map_size.x as f32 + constantes::GAME_BOUNDS_PADDING,
         );
-        let position_y = rand::gen_range(0.0, constantes::GAME_BOUNDS_Y as f32);
+        let position_y = rng.gen_range(0.0, (map_size.y - 1) as f32);
         let position = na::Point2::new(position_x, position_y);
         let sprite = SpriteComponent {
-            texture_index,
+            texture,
             scale,
             ..Default::default()
         };
@@ -44,8 +46,8 @@ impl Cloud {
 
 pub fn render(
     game_state: &mut GameState,
-    sprite_collection: &SpriteCollection,
-    ctx: &mut Context,
+    sprite_collection: &mut SpriteCollection,
+    _ctx: &mut Context,
     screen_size: &na::Point2<f32>,
 ) -> GameResult {
     let mut params = DrawParam::default().offset(mint::Point2 { x: 0.0, y: 0.0 });
@@ -54,27 +56,26 @@ pub fn render(
         let scale = (cloud.sprite.scale * screen_size.x) / 16.0;
         params = params.scale(scale);
         params = params.dest(cloud.position * screen_size.x);
-        let image = sprite_collection
-            .images
-            .get(cloud.sprite.texture_index)
-            .expect("No cloud image...");
-        graphics::draw(ctx, image, params)?;
+        sprite_collection.add(cloud.sprite.texture, params);
     }
     Ok(())
 }
 
-pub fn spawn(game_state: &mut GameState) {
-    let amount = rand::gen_range(constantes::MIN_CLOUDS, constantes::MAX_CLOUDS + 1);
+pub fn spawn(game_state: &mut GameState, rng: &mut GameRng) {
+    let amount = rng.gen_range(constantes::MIN_CLOUDS, constantes::MAX_CLOUDS + 1);
     for _i in 0..amount {
-        game_state.clouds.push(Cloud::new());
+        game_state
+            .clouds
+            .push(Cloud::new(&game_state.map_size, rng));
     }
 }
 
 pub fn update(game_state: &mut GameState, ctx: &mut Context) {
     let delta = ggez::timer::delta(ctx).as_secs_f32();
+    let max_x = game_state.map_size.x as f32 + constantes::GAME_BOUNDS_PADDING;
     for cloud in game_state.clouds.iter_mut() {
         cloud.position.x += delta * cloud.speed;
-        if cloud.position.x > constantes::GAME_BOUNDS_X as f32 + constantes::GAME_BOUNDS_PADDING {
+        if cloud.position.x > max_x {
             cloud.position.x = -constantes::GAME_BOUNDS_PADDING;
         }
     }
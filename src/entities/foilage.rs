@@ -1,24 +1,27 @@
 use crate::constantes;
+use crate::game_rng::GameRng;
 use crate::sprite;
 use crate::states::game_state::GameState;
 use crate::transform_compontent::TransformComponent;
 use nalgebra as na;
-use sprite::{SpriteCollection, SpriteComponent};
+use sprite::{SpriteCollection, SpriteComponent, Texture};
 
 use ggez::graphics;
-use ggez::{rand, Context, GameResult};
+use ggez::{Context, GameResult};
 use graphics::DrawParam;
 use gwg as ggez;
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Grass {
     pub transform: TransformComponent,
     pub sprite: SpriteComponent,
 }
+#[derive(Clone, Copy)]
 pub enum FoilageType {
     Straw, // Rotates
     Bush,  // Stretches
 }
 
+#[derive(Clone)]
 pub struct Foilage {
     pub position: na::Point2<f32>,
     pub pos_i32: na::Point2<i32>, // belongs to this grasss, position
@@ -28,44 +31,45 @@ pub struct Foilage {
 }
 
 impl Foilage {
-    pub fn new(position: na::Point2<f32>, pos_i32: na::Point2<i32>) -> Self {
-        let is_bush = rand::gen_range(0.0, 1.0) < constantes::FOILAGE_BUSH_CHANCE;
+    pub fn new(position: na::Point2<f32>, pos_i32: na::Point2<i32>, rng: &mut GameRng) -> Self {
+        let is_bush = rng.gen_range(0.0, 1.0) < constantes::FOILAGE_BUSH_CHANCE;
         let foilage_type = if is_bush {
             FoilageType::Bush
         } else {
             FoilageType::Straw
         };
-        let texture_index = match foilage_type {
-            FoilageType::Straw => rand::gen_range(14, 16 + 1),
-            FoilageType::Bush => 17,
+        let variant = rng.gen_range(0, sprite::VARIANT_RANGE);
+        let texture = match foilage_type {
+            FoilageType::Straw => Texture::Straw(variant),
+            FoilageType::Bush => Texture::Bush(variant),
         };
 
         Foilage {
             position,
             pos_i32,
             sprite: SpriteComponent {
-                texture_index,
+                texture,
                 scale: na::Vector2::new(1.0, 1.0),
-                is_flipped: rand::gen_range(0, 2) == 0,
+                is_flipped: rng.gen_range(0, 2) == 0,
                 ..Default::default()
             },
             foilage_type,
-            time_offset: rand::gen_range(0.0, 1.0),
+            time_offset: rng.gen_range(0.0, 1.0),
         }
     }
 }
-pub fn generate(game_state: &mut GameState) {
+pub fn generate(game_state: &mut GameState, rng: &mut GameRng) {
     // foilage time!
     // chance to spawn foilage on any grass block
     for grass in game_state
         .grasses
         .iter()
-        .filter(|g| g.sprite.texture_index == 1)
+        .filter(|g| g.sprite.texture == Texture::Grass)
     {
-        if rand::gen_range(0.0, 1.0) > constantes::FOILAGE_SPAWN_CHANCE {
+        if rng.gen_range(0.0, 1.0) > constantes::FOILAGE_SPAWN_CHANCE {
             continue;
         }
-        let foilage_count = rand::gen_range(1, 2 + 1);
+        let foilage_count = rng.gen_range(1, 2 + 1);
         let mut position = na::Point2::new(
             grass.transform.position.x as f32,
             grass.transform.position.y as f32,
@@ -80,13 +84,13 @@ pub fn generate(game_state: &mut GameState) {
             }
             game_state
                 .foilages
-                .push(Foilage::new(position, grass.transform.position));
+                .push(Foilage::new(position, grass.transform.position, rng));
         }
     }
 }
 pub fn render(
     game_state: &mut GameState,
-    sprite_collection: &SpriteCollection,
+    sprite_collection: &mut SpriteCollection,
     ctx: &mut Context,
     screen_size: &na::Point2<f32>,
 ) -> GameResult {
@@ -129,11 +133,7 @@ pub fn render(
             ))
             .rotation(rotation)
             .dest(dest);
-        let image = sprite_collection
-            .images
-            .get(foilage.sprite.texture_index)
-            .expect("No image with id...");
-        graphics::draw(ctx, image, params)?;
+        sprite_collection.add(foilage.sprite.texture, params);
     }
     Ok(())
 }
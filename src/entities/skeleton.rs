@@ -1,22 +1,19 @@
-use crate::constantes;
 use crate::entities::ai::{AiComponent, AiState};
-use crate::particle_system::ParticleSystemCollection;
-use crate::sound_collection::SoundCollection;
-use crate::sprite::SpriteComponent;
+use crate::events::{EventBus, GameEvent};
+use crate::sprite::{SpriteComponent, Texture};
 use crate::states::game_state::GameState;
 use crate::transform_compontent::TransformComponent;
-use ggez::Context;
-use gwg as ggez;
 use nalgebra as na;
 use std::collections::HashMap;
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Skeleton {
     pub transform: TransformComponent,
     pub sprite: SpriteComponent,
     pub ai: AiComponent,
+    pub is_just_released: bool, // shows the attack sprite until its first attack, only the looks
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct SkeletonBlock {
     pub transform: TransformComponent,
     pub sprite: SpriteComponent,
@@ -26,7 +23,6 @@ pub struct SkeletonBlock {
 impl SkeletonBlock {
     pub fn dig(&mut self) {
         self.buried.is_dug = true;
-        self.sprite.texture_index = 2;
     }
 
     pub fn try_release(&mut self) -> bool {
@@ -41,14 +37,11 @@ impl SkeletonBlock {
     }
 }
 
-pub fn block_system(game_state: &mut GameState, sound_collection: &mut SoundCollection) {
+pub fn block_system(game_state: &mut GameState, events: &mut EventBus) {
     for block in game_state.skeleton_blocks.iter_mut() {
         let pos_above = block.transform.position - na::Vector2::new(0, 1);
         let mut is_occupied = game_state.player.transform.position == pos_above;
-        is_occupied |= game_state
-            .skeletons
-            .iter()
-            .any(|s| s.transform.position == pos_above);
+        is_occupied |= game_state.grid.has_skeleton(&pos_above);
         if is_occupied {
             continue;
         }
@@ -56,24 +49,23 @@ pub fn block_system(game_state: &mut GameState, sound_collection: &mut SoundColl
             let transform = TransformComponent {
                 position: pos_above,
             };
-            let sprite = SpriteComponent {
-                texture_index: 7,
-                ..Default::default()
-            };
-            let mut new_skeleton = Skeleton {
+            let new_skeleton = Skeleton {
                 transform,
-                sprite,
+                is_just_released: true,
                 ..Default::default()
             };
             let delta_player_x =
                 game_state.player.transform.position.x - new_skeleton.transform.position.x;
-            if delta_player_x > 0 {
-                new_skeleton.sprite.is_flipped = false;
-            } else {
-                new_skeleton.sprite.is_flipped = true;
-            }
+            let new_index = game_state.skeletons.len();
+            events.emit(GameEvent::SkeletonReleased {
+                skeleton: new_index,
+            });
+            events.emit(GameEvent::SkeletonTurned {
+                skeleton: new_index,
+                is_flipped: delta_player_x <= 0,
+            });
             game_state.skeletons.push(new_skeleton);
-            sound_collection.play(5);
+            game_state.grid.add_skeleton(&pos_above);
         }
     }
 }
@@ -84,11 +76,7 @@ pub fn reset_turns(game_state: &mut GameState) {
     }
 }
 
-pub fn walk(
-    game_state: &mut GameState,
-    sound_collection: &mut SoundCollection,
-    screen_size: &na::Point2<f32>,
-) {
+pub fn walk(game_state: &mut GameState, events: &mut EventBus) {
     let pos_player = game_state.player.transform.position;
     let mut new_positions = HashMap::new();
     let mut wants_attack: Vec<usize> = vec![];
@@ -104,18 +92,7 @@ pub fn walk(
         let mut new_position = skeleton.transform.position;
         let pos_below = skeleton.transform.position + na::Vector2::new(0, 1);
         let mut is_grounded = pos_player == pos_below;
-        is_grounded |= game_state
-            .grasses
-            .iter()
-            .any(|g| g.transform.position == pos_below);
-        is_grounded |= game_state
-            .skeletons
-            .iter()
-            .any(|s| s.transform.position == pos_below);
-        is_grounded |= game_state
-            .skeleton_blocks
-            .iter()
-            .any(|s| s.transform.position == pos_below);
+        is_grounded |= game_state.grid.is_solid(&pos_below);
 
         // walk towards player
         if is_grounded {
@@ -130,32 +107,14 @@ pub fn walk(
             let mut is_occupied = pos_skele == pos_player;
             if is_occupied {
                 wants_attack.push(index);
-                sound_collection.play(5);
                 continue;
             }
-            is_occupied |= game_state
-                .grasses
-                .iter()
-                .any(|g| g.transform.position == pos_skele);
-            is_occupied |= game_state
-                .skeleton_blocks
-                .iter()
-                .any(|s| s.transform.position == pos_skele);
-            is_occupied |= game_state
-                .skeletons
-                .iter()
-                .any(|s| s.transform.position == pos_skele);
+            is_occupied |= game_state.grid.is_solid(&pos_skele);
             let up_vector = na::Vector2::new(0, -1);
-            let is_other_falling = game_state
-                .skeletons
-                .iter()
-                .any(|s| s.transform.position == pos_skele + up_vector);
+            let is_other_falling = game_state.grid.has_skeleton(&(pos_skele + up_vector));
 
             let pos_above_skeleton = skeleton.transform.position + up_vector;
-            let is_above = game_state
-                .skeletons
-                .iter()
-                .any(|s| s.transform.position == pos_above_skeleton);
+            let is_above = game_state.grid.has_skeleton(&pos_above_skeleton);
 
             if !is_occupied && !game_state.player.is_on_skeleton && !is_above && !is_other_falling {
                 new_position = pos_skele;
@@ -165,7 +124,7 @@ pub fn walk(
             new_position = pos_below;
         }
 
-        let warped_y = in_bounds(&mut new_position);
+        let warped_y = in_bounds(&mut new_position, &game_state.map_size);
         if warped_y {
             skeleton_warped_y.push(index);
         }
@@ -179,6 +138,7 @@ pub fn walk(
     for (i, skeleton) in game_state.skeletons.iter_mut().enumerate() {
         match new_positions.get(&i) {
             Some(p) => {
+                game_state.grid.move_skeleton(&skeleton.transform.position, p);
                 skeleton.transform.position = *p;
             }
             None => {}
@@ -186,39 +146,25 @@ pub fn walk(
     }
     for i in wants_attack.iter() {
         match game_state.skeletons.get_mut(*i) {
-            Some(skeleton) => {
-                skeleton.sprite.texture_index = 7;
-                skeleton.ai.state = AiState::Attack
-            }
+            Some(skeleton) => skeleton.ai.state = AiState::Attack,
             None => {}
         }
     }
-    for (i, flipped) in flip_dirs.iter() {
-        if let Some(skeleton) = game_state.skeletons.get_mut(*i) {
-            skeleton.sprite.is_flipped = *flipped;
-        }
+    for skeleton in wants_attack {
+        events.emit(GameEvent::SkeletonAttacking { skeleton });
     }
-    for i in skeleton_warped_y.iter() {
-        match game_state.skeletons.get_mut(*i) {
-            Some(skeleton) => {
-                skeleton.sprite.blink_timer = constantes::TIME_BLINK;
-                let position =
-                    na::convert::<na::Point2<i32>, na::Point2<f32>>(skeleton.transform.position);
-                skeleton.sprite.visual_position = position * screen_size.x;
-            }
-            None => {}
-        }
+    for (skeleton, is_flipped) in flip_dirs {
+        events.emit(GameEvent::SkeletonTurned {
+            skeleton,
+            is_flipped,
+        });
+    }
+    for skeleton in skeleton_warped_y {
+        events.emit(GameEvent::SkeletonWarped { skeleton });
     }
 }
 
-pub fn attack(
-    game_state: &mut GameState,
-    _ctx: &mut Context,
-    sound_collection: &mut SoundCollection,
-    particle_collection: &mut ParticleSystemCollection,
-    blood_id: &u32,
-    screen_size: &na::Point2<f32>,
-) {
+pub fn attack(game_state: &mut GameState, events: &mut EventBus) {
     let player = &mut game_state.player;
     let pos_player = &player.transform.position;
     for skeleton in game_state
@@ -234,70 +180,61 @@ pub fn attack(
             pos_skele_to_player.x -= 1;
         }
         let attack_player = pos_skele_to_player == *pos_player;
+        skeleton.is_just_released = false;
         match attack_player {
             true => {
                 player.is_alive = false;
-                player.sprite.texture_index = 9;
                 skeleton.ai.state = AiState::Walk;
-                skeleton.sprite.texture_index = 4;
-                sound_collection.play(2);
-
-                let blood_particles = particle_collection.get_mut(*blood_id).unwrap();
-                blood_particles.scale = screen_size.x / 16.0;
-                let pos_player_visual = player.sprite.visual_position;
-                let mut pos_particle = na::Vector2::new(
-                    pos_player_visual.x / screen_size.x * 16.0,
-                    pos_player_visual.y / screen_size.x * 16.0,
-                );
-                pos_particle += na::Vector2::new(16.0 * 0.5, 16.0 * 0.5);
-
-                blood_particles.position = pos_particle;
-                blood_particles.emit(20);
+                events.emit(GameEvent::PlayerKilled {
+                    position: *pos_player,
+                });
             }
             false => {
                 skeleton.ai.state = AiState::Walk;
-                skeleton.sprite.texture_index = 4;
             }
         }
         skeleton.ai.turn_taken = true;
     }
 }
 
-pub fn system(
-    game_state: &mut GameState,
-    ctx: &mut Context,
-    sound_collection: &mut SoundCollection,
-    particle_collection: &mut ParticleSystemCollection,
-    blood_id: &u32,
-    screen_size: &na::Point2<f32>,
-) {
-    attack(
-        game_state,
-        ctx,
-        sound_collection,
-        particle_collection,
-        blood_id,
-        screen_size,
-    );
-    walk(game_state, sound_collection, screen_size);
+pub fn system(game_state: &mut GameState, events: &mut EventBus) {
+    attack(game_state, events);
+    walk(game_state, events);
     reset_turns(game_state);
 }
 
+// Skeleton sprites only reflect state, the rules never touch them
+pub fn update_sprites(game_state: &mut GameState) {
+    for skeleton in game_state.skeletons.iter_mut() {
+        let is_attacking = skeleton.ai.state == AiState::Attack || skeleton.is_just_released;
+        skeleton.sprite.texture = match is_attacking {
+            true => Texture::SkeletonAttack,
+            false => Texture::Skeleton,
+        };
+    }
+    for block in game_state.skeleton_blocks.iter_mut() {
+        block.sprite.texture = match block.buried.is_dug {
+            true => Texture::SkeletonBlockDug,
+            false => Texture::SkeletonBlock,
+        };
+    }
+}
+
 // Returns if the skeleton warped y
-pub fn in_bounds(position: &mut na::Point2<i32>) -> bool {
+pub fn in_bounds(position: &mut na::Point2<i32>, map_size: &na::Point2<i32>) -> bool {
     if position.x < 0 {
         position.x = 0;
-    } else if position.x > constantes::GAME_BOUNDS_X {
-        position.x = constantes::GAME_BOUNDS_X;
+    } else if position.x >= map_size.x {
+        position.x = map_size.x - 1;
     }
-    if position.y > constantes::GAME_BOUNDS_Y {
+    if position.y >= map_size.y {
         position.y = 0;
         return true;
     }
     false
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct BuriedComponent {
     pub is_dug: bool,
     pub is_released: bool,
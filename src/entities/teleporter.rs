@@ -1,11 +1,11 @@
 use crate::{sprite::SpriteComponent, transform_compontent::TransformComponent};
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Teleporter {
     pub transform: TransformComponent,
     pub sprite: SpriteComponent,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Exit {
     pub transform: TransformComponent,
     pub sprite: SpriteComponent,
@@ -0,0 +1,65 @@
+use crate::entities::player::PlayerInputIntent;
+use crate::sprite::Texture;
+use nalgebra as na;
+
+// Everything the rules report during a turn, positions are in tiles
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    PlayerActed(PlayerInputIntent), // stood on ground and the intent was resolved
+    PlayerStepped {
+        from: na::Point2<i32>,
+        to: na::Point2<i32>,
+    },
+    PlayerDug {
+        position: na::Point2<i32>, // the tile under the player
+        foilage: Option<Texture>,  // sprite of the foilage that was dug away
+    },
+    PlayerStartedFalling,
+    PlayerWarped, // fell out of the bottom of the map and came back at the top
+    Landed {
+        position: na::Point2<i32>,
+    },
+    Teleported {
+        teleporter: usize, // index of the teleporter the player came out of
+    },
+    ExitLocked,
+    LevelCompleted,
+    PlayerKilled {
+        position: na::Point2<i32>,
+    },
+    SkeletonReleased {
+        skeleton: usize,
+    },
+    SkeletonAttacking {
+        skeleton: usize,
+    },
+    SkeletonTurned {
+        skeleton: usize,
+        is_flipped: bool,
+    },
+    SkeletonWarped {
+        skeleton: usize,
+    },
+}
+
+// The rules emit events here while a turn runs, they never play sounds or touch sprites.
+// Whoever runs the simulation hands the events to its listeners (audio, particles, UI),
+// headless code like the solver only looks at them
+#[derive(Default)]
+pub struct EventBus {
+    events: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn contains(&self, event: &GameEvent) -> bool {
+        self.events.contains(event)
+    }
+}
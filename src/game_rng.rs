@@ -0,0 +1,66 @@
+use rand::distributions::uniform::SampleUniform;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// The one source of randomness for the look of a level: foilage, clouds and particles,
+// and for the map generator.
+// It's reseeded with the level seed on every attempt, so a replay or a screenshot of the
+// same level and seed gets the same decoration and the same particles.
+// ChaCha gives the same numbers on every platform and rand version, unlike StdRng
+pub struct GameRng {
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    // Between the two ends, which can come in any order, presets can also use the same value for both
+    pub fn gen_range<T: SampleUniform + PartialOrd>(&mut self, from: T, to: T) -> T {
+        if from < to {
+            self.rng.gen_range(from, to)
+        } else if to < from {
+            self.rng.gen_range(to, from)
+        } else {
+            from
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(rng: &mut GameRng) -> Vec<u32> {
+        (0..8).map(|_| rng.gen_range(0, 1000)).collect()
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let first = numbers(&mut GameRng::new(42));
+        assert_eq!(first, numbers(&mut GameRng::new(42)));
+        assert_ne!(first, numbers(&mut GameRng::new(43)));
+
+        let mut rng = GameRng::new(7);
+        numbers(&mut rng);
+        rng.reseed(42);
+        assert_eq!(first, numbers(&mut rng));
+    }
+
+    #[test]
+    fn ends_in_any_order() {
+        let mut rng = GameRng::new(1);
+        for _ in 0..100 {
+            let value = rng.gen_range(166.0, 54.0);
+            assert!((54.0..166.0).contains(&value));
+        }
+        assert_eq!(rng.gen_range(3, 3), 3);
+    }
+}
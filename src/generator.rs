@@ -0,0 +1,383 @@
+use crate::constantes;
+use crate::entities::player::PlayerInputIntent;
+use crate::game_rng::GameRng;
+use crate::map::{self, MapData, Tile};
+use crate::solver::{self, SolveResult};
+use crate::states::game_state::GameState;
+use nalgebra as na;
+use std::fmt;
+
+// Solver budget per candidate, generated maps are small so anything past this is
+// more work than a player wants to put in anyway
+const MAX_STATES: usize = 50_000;
+
+// Generating from the editor runs inside a key press, so it gets a much smaller budget
+// to keep the frame short. A miss just asks to press the key again
+const EDITOR_MAX_ATTEMPTS: usize = 100;
+const EDITOR_MAX_STATES: usize = 5_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+// What a generated map looks like, densities are chances between 0 and 1
+#[derive(Clone, Debug)]
+pub struct GeneratorSettings {
+    pub width: i32,
+    pub height: i32,
+    pub platform_density: f32, // chance for each row to get a platform, tried twice per row
+    pub dirt_density: f32,     // chance for each tile under a platform to be filled
+    pub skeleton_blocks: usize,
+    pub teleporters: bool,
+    // Difficulty target, the length of the shortest solution
+    pub min_moves: usize,
+    pub max_moves: usize,
+    pub max_attempts: usize,
+    pub max_states: usize, // solver budget per candidate
+}
+
+impl GeneratorSettings {
+    pub fn new(difficulty: Difficulty) -> Self {
+        let settings = GeneratorSettings {
+            width: constantes::DEFAULT_MAP_WIDTH,
+            height: constantes::DEFAULT_MAP_HEIGHT,
+            platform_density: 0.6,
+            dirt_density: 0.5,
+            skeleton_blocks: 1,
+            teleporters: false,
+            min_moves: 6,
+            max_moves: 12,
+            max_attempts: 500,
+            max_states: MAX_STATES,
+        };
+        match difficulty {
+            Difficulty::Easy => settings,
+            Difficulty::Medium => GeneratorSettings {
+                skeleton_blocks: 2,
+                min_moves: 12,
+                max_moves: 20,
+                ..settings
+            },
+            Difficulty::Hard => GeneratorSettings {
+                platform_density: 0.7,
+                skeleton_blocks: 3,
+                teleporters: true,
+                min_moves: 20,
+                max_moves: 40,
+                max_attempts: 2000,
+                ..settings
+            },
+        }
+    }
+
+    // Platforms are at least 2 tiles long and half the width at most, so narrower maps can't be made
+    pub fn check(&self) -> Result<(), String> {
+        let max = constantes::MAX_MAP_SIZE;
+        if self.width < 4 || self.width > max {
+            return Err(format!("width {} isn't between 4 and {}", self.width, max));
+        }
+        if self.height < 2 || self.height > max {
+            return Err(format!(
+                "height {} isn't between 2 and {}",
+                self.height, max
+            ));
+        }
+        if self.min_moves > self.max_moves {
+            return Err(format!(
+                "min moves {} is above max moves {}",
+                self.min_moves, self.max_moves
+            ));
+        }
+        Ok(())
+    }
+
+    // Same maps as new(), with a budget small enough to generate between two frames
+    pub fn for_editor(difficulty: Difficulty) -> Self {
+        GeneratorSettings {
+            max_attempts: EDITOR_MAX_ATTEMPTS,
+            max_states: EDITOR_MAX_STATES,
+            ..GeneratorSettings::new(difficulty)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    InvalidSettings(String),
+    NotFound(usize), // no candidate fit the settings after this many attempts
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorError::InvalidSettings(e) => write!(f, "invalid settings, {}", e),
+            GeneratorError::NotFound(attempts) => {
+                write!(f, "no map fit the settings after {} attempts", attempts)
+            }
+        }
+    }
+}
+
+pub struct GeneratedMap {
+    pub map_data: MapData,
+    pub seed: u64,
+    pub solution: Vec<PlayerInputIntent>,
+    pub attempts: usize,
+}
+
+// A map while it's being generated
+struct Layout {
+    width: i32,
+    height: i32,
+    tiles: Vec<Option<Tile>>,
+}
+
+impl Layout {
+    fn get(&self, x: i32, y: i32) -> Option<Tile> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        self.tiles[(y * self.width + x) as usize] = Some(tile);
+    }
+
+    fn is_empty(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_none()
+    }
+
+    // Empty tiles with ground right under them, where the player can stand
+    fn standing_spots(&self) -> Vec<(i32, i32)> {
+        let mut spots = vec![];
+        for y in 0..self.height - 1 {
+            for x in 0..self.width {
+                let ground = self.get(x, y + 1);
+                if self.is_empty(x, y) && ground.is_some() && ground != Some(Tile::Teleporter) {
+                    spots.push((x, y));
+                }
+            }
+        }
+        spots
+    }
+
+    fn take_random(&self, rng: &mut GameRng, spots: &mut Vec<(i32, i32)>) -> Option<(i32, i32)> {
+        spots.retain(|(x, y)| self.is_empty(*x, *y));
+        if spots.is_empty() {
+            return None;
+        }
+        let index = rng.gen_range(0, spots.len());
+        Some(spots.swap_remove(index))
+    }
+
+    fn map_data(&self) -> MapData {
+        let mut tiles = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(tile) = self.get(x, y) {
+                    tiles.push((na::Point2::new(x, y), tile));
+                }
+            }
+        }
+        MapData {
+            width: self.width,
+            height: self.height,
+            tiles,
+        }
+    }
+}
+
+// One random candidate, None if the special tiles didn't fit
+fn generate_candidate(settings: &GeneratorSettings, rng: &mut GameRng) -> Option<MapData> {
+    let mut layout = Layout {
+        width: settings.width,
+        height: settings.height,
+        tiles: vec![None; (settings.width * settings.height) as usize],
+    };
+
+    // Terrain, grass platforms with dirt under them
+    for y in 1..settings.height {
+        for _ in 0..2 {
+            if rng.gen_range(0.0, 1.0) >= settings.platform_density {
+                continue;
+            }
+            let length = rng.gen_range(2, settings.width / 2 + 1);
+            let start = rng.gen_range(0, settings.width - length + 1);
+            for x in start..start + length {
+                if layout.is_empty(x, y) {
+                    // Grass only grows in the open, covered tiles are dirt
+                    let tile = match layout.is_empty(x, y - 1) {
+                        true => Tile::Grass,
+                        false => Tile::Dirt,
+                    };
+                    layout.set(x, y, tile);
+                }
+                for below in y + 1..settings.height {
+                    if !layout.is_empty(x, below)
+                        || rng.gen_range(0.0, 1.0) >= settings.dirt_density
+                    {
+                        break;
+                    }
+                    layout.set(x, below, Tile::Dirt);
+                }
+            }
+        }
+    }
+
+    // Skeletons are buried in the surface, where the player can dig them out
+    let mut surface: Vec<(i32, i32)> = layout
+        .standing_spots()
+        .into_iter()
+        .map(|(x, y)| (x, y + 1))
+        .collect();
+    for _ in 0..settings.skeleton_blocks {
+        surface.retain(|(x, y)| layout.get(*x, *y) != Some(Tile::SkeletonBlock));
+        if surface.is_empty() {
+            return None;
+        }
+        let (x, y) = surface.swap_remove(rng.gen_range(0, surface.len()));
+        layout.set(x, y, Tile::SkeletonBlock);
+    }
+
+    let mut spots = layout.standing_spots();
+    if settings.teleporters {
+        for _ in 0..2 {
+            let (x, y) = layout.take_random(rng, &mut spots)?;
+            layout.set(x, y, Tile::Teleporter);
+        }
+    }
+    let (x, y) = layout.take_random(rng, &mut spots)?;
+    layout.set(x, y, Tile::Exit);
+    let (x, y) = layout.take_random(rng, &mut spots)?;
+    layout.set(x, y, Tile::Player);
+
+    Some(layout.map_data())
+}
+
+// Keeps generating candidates until one can be won in the target number of moves.
+// Every candidate is played by the solver with the real game rules, so a map is only
+// kept if the exit can be reached after every skeleton has been released.
+// GameRng is the same on every platform, so a seed always makes the same map
+pub fn generate(settings: &GeneratorSettings, seed: u64) -> Result<GeneratedMap, GeneratorError> {
+    settings.check().map_err(GeneratorError::InvalidSettings)?;
+    let mut rng = GameRng::new(seed);
+    for attempt in 1..=settings.max_attempts {
+        let candidate = match generate_candidate(settings, &mut rng) {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+        // Same checks as a map read from a file
+        let map_data = match map::parse_map(&map::map_to_string(&candidate)) {
+            Ok(map_data) => map_data,
+            Err(_) => continue,
+        };
+        let mut game_state = GameState::new();
+        map::build_map(&mut game_state, &map_data);
+        if let SolveResult::Solved(solution) = solver::solve(&game_state, settings.max_states) {
+            if solution.len() >= settings.min_moves && solution.len() <= settings.max_moves {
+                return Ok(GeneratedMap {
+                    map_data,
+                    seed,
+                    solution,
+                    attempts: attempt,
+                });
+            }
+        }
+    }
+    Err(GeneratorError::NotFound(settings.max_attempts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_settings_are_an_error() {
+        let easy = GeneratorSettings::new(Difficulty::Easy);
+        let settings = [
+            GeneratorSettings {
+                width: 3,
+                ..easy.clone()
+            },
+            GeneratorSettings {
+                width: 0,
+                ..easy.clone()
+            },
+            GeneratorSettings {
+                width: -5,
+                ..easy.clone()
+            },
+            GeneratorSettings {
+                height: 1,
+                ..easy.clone()
+            },
+            GeneratorSettings {
+                height: 1000,
+                ..easy.clone()
+            },
+            GeneratorSettings {
+                min_moves: 20,
+                max_moves: 10,
+                ..easy.clone()
+            },
+        ];
+        for settings in settings.iter() {
+            assert!(matches!(
+                generate(settings, 1),
+                Err(GeneratorError::InvalidSettings(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn narrowest_map() {
+        let settings = GeneratorSettings {
+            width: 4,
+            height: 4,
+            min_moves: 1,
+            ..GeneratorSettings::new(Difficulty::Easy)
+        };
+        for seed in 0..20 {
+            match generate(&settings, seed) {
+                Ok(generated) => assert_eq!(generated.map_data.width, 4),
+                Err(e) => assert!(matches!(e, GeneratorError::NotFound(_)), "{}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let settings = GeneratorSettings::for_editor(Difficulty::Easy);
+        let first = generate(&settings, 7).unwrap();
+        let second = generate(&settings, 7).unwrap();
+        assert_eq!(
+            map::map_to_string(&first.map_data),
+            map::map_to_string(&second.map_data)
+        );
+        assert!(first.solution.len() >= settings.min_moves);
+        assert!(first.solution.len() <= settings.max_moves);
+    }
+}
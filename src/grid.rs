@@ -0,0 +1,103 @@
+use crate::constantes;
+use crate::states::game_state::GameState;
+use nalgebra as na;
+
+// Everything the rules need to know about one tile
+#[derive(Default, Clone, Copy)]
+pub struct Cell {
+    pub grass: bool,
+    pub skeleton_block: Option<usize>, // index into game_state.skeleton_blocks
+    pub skeletons: u8,                 // a skeleton warping to the top can share a tile for a turn
+}
+
+// Occupancy of every tile, so collision checks are lookups instead of scanning every entity.
+// Grass and skeleton blocks never move, skeletons update it when they spawn or move.
+// Positions outside the map are always empty
+#[derive(Clone)]
+pub struct OccupancyGrid {
+    size: na::Point2<i32>,
+    cells: Vec<Cell>,
+}
+
+impl OccupancyGrid {
+    // Maps are at most MAX_MAP_SIZE tiles each way, positions past that are outside the grid
+    pub fn new(size: na::Point2<i32>) -> Self {
+        let clamp = |n: i32| n.max(0).min(constantes::MAX_MAP_SIZE);
+        let size = na::Point2::new(clamp(size.x), clamp(size.y));
+        OccupancyGrid {
+            size,
+            cells: vec![Cell::default(); (size.x * size.y) as usize],
+        }
+    }
+
+    fn index(&self, position: &na::Point2<i32>) -> Option<usize> {
+        if position.x < 0 || position.y < 0 || position.x >= self.size.x || position.y >= self.size.y
+        {
+            return None;
+        }
+        Some((position.y * self.size.x + position.x) as usize)
+    }
+
+    pub fn cell(&self, position: &na::Point2<i32>) -> Cell {
+        match self.index(position) {
+            Some(index) => self.cells[index],
+            None => Cell::default(),
+        }
+    }
+
+    fn cell_mut(&mut self, position: &na::Point2<i32>) -> Option<&mut Cell> {
+        let index = self.index(position)?;
+        self.cells.get_mut(index)
+    }
+
+    // Grass, skeletons and skeleton blocks are solid
+    pub fn is_solid(&self, position: &na::Point2<i32>) -> bool {
+        let cell = self.cell(position);
+        cell.grass || cell.skeleton_block.is_some() || cell.skeletons > 0
+    }
+
+    pub fn has_skeleton(&self, position: &na::Point2<i32>) -> bool {
+        self.cell(position).skeletons > 0
+    }
+
+    pub fn skeleton_block_at(&self, position: &na::Point2<i32>) -> Option<usize> {
+        self.cell(position).skeleton_block
+    }
+
+    pub fn add_skeleton(&mut self, position: &na::Point2<i32>) {
+        if let Some(cell) = self.cell_mut(position) {
+            cell.skeletons += 1;
+        }
+    }
+
+    pub fn remove_skeleton(&mut self, position: &na::Point2<i32>) {
+        if let Some(cell) = self.cell_mut(position) {
+            cell.skeletons = cell.skeletons.saturating_sub(1);
+        }
+    }
+
+    pub fn move_skeleton(&mut self, from: &na::Point2<i32>, to: &na::Point2<i32>) {
+        self.remove_skeleton(from);
+        self.add_skeleton(to);
+    }
+}
+
+// Builds the grid from scratch, needed whenever entities are replaced wholesale
+// (loading a map, undo/redo)
+pub fn rebuild(game_state: &mut GameState) {
+    let mut grid = OccupancyGrid::new(game_state.map_size);
+    for grass in game_state.grasses.iter() {
+        if let Some(cell) = grid.cell_mut(&grass.transform.position) {
+            cell.grass = true;
+        }
+    }
+    for (index, block) in game_state.skeleton_blocks.iter().enumerate() {
+        if let Some(cell) = grid.cell_mut(&block.transform.position) {
+            cell.skeleton_block = Some(index);
+        }
+    }
+    for skeleton in game_state.skeletons.iter() {
+        grid.add_skeleton(&skeleton.transform.position);
+    }
+    game_state.grid = grid;
+}
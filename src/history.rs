@@ -0,0 +1,99 @@
+use crate::entities::{
+    foilage::Foilage,
+    player::{Player, PlayerInputIntent},
+    skeleton::{Skeleton, SkeletonBlock},
+};
+use crate::grid;
+use crate::states::game_state::GameState;
+
+// Everything a turn can change, taken right before the turn is resolved
+// together with the move that was made from here
+pub struct Snapshot {
+    turn: u32,
+    move_count: u32,
+    intent: PlayerInputIntent,
+    player: Player,
+    skeletons: Vec<Skeleton>,
+    skeleton_blocks: Vec<SkeletonBlock>,
+    foilages: Vec<Foilage>,
+}
+
+impl Snapshot {
+    pub fn take(game_state: &GameState, intent: PlayerInputIntent) -> Self {
+        Snapshot {
+            turn: game_state.turn,
+            move_count: game_state.move_count,
+            intent,
+            player: game_state.player.clone(),
+            skeletons: game_state.skeletons.clone(),
+            skeleton_blocks: game_state.skeleton_blocks.clone(),
+            foilages: game_state.foilages.clone(),
+        }
+    }
+
+    pub fn restore(self, game_state: &mut GameState) {
+        game_state.turn = self.turn;
+        game_state.move_count = self.move_count;
+        game_state.player = self.player;
+        game_state.skeletons = self.skeletons;
+        game_state.skeleton_blocks = self.skeleton_blocks;
+        game_state.foilages = self.foilages;
+        grid::rebuild(game_state);
+    }
+}
+
+// Undo/redo stacks of snapshots, one per player move
+// Falling steps are never recorded, so they are undone together with the move that caused them
+pub struct History {
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            undo_stack: vec![],
+            redo_stack: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    // Call before resolving a move, a new move makes the redo stack invalid
+    pub fn record(&mut self, game_state: &GameState, intent: PlayerInputIntent) {
+        self.undo_stack.push(Snapshot::take(game_state, intent));
+        self.redo_stack.clear();
+    }
+
+    // Every move that led to the current state, with the turn it was made on
+    pub fn moves(&self) -> Vec<(u32, PlayerInputIntent)> {
+        self.undo_stack.iter().map(|s| (s.turn, s.intent)).collect()
+    }
+
+    // returns if anything was undone
+    pub fn undo(&mut self, game_state: &mut GameState) -> bool {
+        match self.undo_stack.pop() {
+            Some(snapshot) => {
+                self.redo_stack.push(Snapshot::take(game_state, snapshot.intent));
+                snapshot.restore(game_state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // returns if anything was redone
+    pub fn redo(&mut self, game_state: &mut GameState) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                self.undo_stack.push(Snapshot::take(game_state, snapshot.intent));
+                snapshot.restore(game_state);
+                true
+            }
+            None => false,
+        }
+    }
+}
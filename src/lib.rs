@@ -0,0 +1,25 @@
+pub mod constantes;
+pub mod sprite;
+pub mod transform_compontent;
+
+pub mod assets;
+pub mod campaign;
+pub mod effects;
+pub mod entities;
+pub mod events;
+pub mod game_rng;
+pub mod generator;
+pub mod grid;
+pub mod history;
+pub mod map;
+pub mod particle_system;
+pub mod progress;
+pub mod replay;
+pub mod simulation;
+pub mod solver;
+pub mod sound_collection;
+pub mod speedrun;
+pub mod storage;
+pub mod util;
+
+pub mod states;
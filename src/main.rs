@@ -1,15 +1,5 @@
-mod constantes;
-mod sprite;
-mod transform_compontent;
-
-mod entities;
-mod map;
-mod particle_system;
-mod sound_collection;
-mod util;
-
-mod states;
-use states::main_state::MainState;
+use dig_escape::replay::Replay;
+use dig_escape::states::main_state::MainState;
 
 // Magic!
 use gwg as ggez;
@@ -23,6 +13,29 @@ fn main() -> GameResult {
             loading: ggez::conf::Loading::Embedded,
             ..Default::default()
         }, // conf
-        |mut context| Box::new(MainState::new(&mut context).unwrap()),
+        |mut context| {
+            let mut main_state = MainState::new(&mut context).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            if let Some(replay) = replay_from_args() {
+                main_state.start_playback(&mut context, replay);
+            }
+            Box::new(main_state)
+        },
     ) // ggez::start
 }
+
+// dig_escape --replay replays/map_middleclash_2_1234.txt
+fn replay_from_args() -> Option<Replay> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--replay")?;
+    let path = args.get(index + 1)?;
+    match Replay::load(path) {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            eprintln!("could not load replay {}", e);
+            None
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use ggez::Context;
 use gwg as ggez;
 
+use crate::constantes;
 use crate::entities::teleporter::{Exit, Teleporter};
 use crate::entities::{cloud, foilage, player, skeleton};
-use crate::sprite::SpriteComponent;
+use crate::game_rng::GameRng;
+use crate::grid;
+use crate::sprite::{SpriteComponent, Texture};
 use crate::states::game_state::GameState;
 use crate::transform_compontent::TransformComponent;
 use crate::util;
@@ -11,42 +14,118 @@ use foilage::Grass;
 use nalgebra as na;
 use player::Player;
 use skeleton::SkeletonBlock;
+use std::fmt;
 use std::io::Read;
 
-const MAP_NAMES: &[&str] = &[
-    "/maps/map_first.txt",
-    "/maps/map_1skeleton.txt",
-    "/maps/map_2skeleton.txt",
-    "/maps/map_gravity.txt",
-    "/maps/map_teleport.txt",
-    "/maps/map_simple_backtrack.txt",
-    "/maps/map_follow.txt",
-    "/maps/map_middleclash.txt",
-    "/maps/map_2skeleton_intro.txt",
-    "/maps/map_2skeleton_backtrack.txt",
-    "/maps/map_force_stand.txt",
-    "maps/map_fall_trap.txt",
-    "/maps/map_skeleton_hole.txt",
-    "/maps/map_easy_accidental_fall.txt",
-    "/maps/map_easy3.txt",
-    "/maps/map_easy1.txt",
-    "/maps/map_maze1.txt",
-    "/maps/map_follow_2.txt",
-    "/maps/map_easy2.txt",
-    "/maps/map_3skeleton.txt",
-    "/maps/map_3skeleton_3holes.txt",
-    "/maps/map_skeleton_platform.txt",
-    "/maps/map_middleclash_2.txt",
-    "/maps/map_3skeleton_3holes_harder.txt",
-    "/maps/map_hard1.txt",
-    "/maps/map_hard3.txt",
-    "/maps/map_hard2.txt",
-];
-
-pub const MAP_COUNT: usize = MAP_NAMES.len();
-
-fn get_map_name(index: usize) -> &'static str {
-    MAP_NAMES[index]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tile {
+    Player,
+    Grass,
+    SkeletonBlock,
+    Teleporter,
+    Exit,
+    Dirt,
+}
+
+impl Tile {
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            '0' => Some(Tile::Player),
+            '1' => Some(Tile::Grass),
+            '2' => Some(Tile::SkeletonBlock),
+            '3' => Some(Tile::Teleporter),
+            '4' => Some(Tile::Exit),
+            '5' => Some(Tile::Dirt),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Player => '0',
+            Tile::Grass => '1',
+            Tile::SkeletonBlock => '2',
+            Tile::Teleporter => '3',
+            Tile::Exit => '4',
+            Tile::Dirt => '5',
+        }
+    }
+
+    // Sprite the tile is drawn with before the game animates it
+    pub fn texture(self) -> Texture {
+        match self {
+            Tile::Player => Texture::Player,
+            Tile::Grass => Texture::Grass,
+            Tile::SkeletonBlock => Texture::SkeletonBlock,
+            Tile::Teleporter => Texture::Teleporter,
+            Tile::Exit => Texture::Exit,
+            Tile::Dirt => Texture::Dirt,
+        }
+    }
+}
+
+// A parsed map, every tile that isn't empty
+// Guaranteed to have one player, one exit and zero or two teleporters
+// The size comes from a 'size width height' first line, maps without one are 10x8
+#[derive(Clone)]
+pub struct MapData {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<(na::Point2<i32>, Tile)>,
+}
+
+#[derive(Debug)]
+pub enum MapErrorKind {
+    NotFound(String),
+    InvalidSize,
+    UnknownTile(char),
+    OutOfBounds(char),
+    DuplicatePlayer,
+    DuplicateExit,
+    TooManyTeleporters,
+    UnpairedTeleporter,
+    MissingPlayer,
+    MissingExit,
+}
+
+// line and column start at 1, like a text editor shows them
+#[derive(Debug)]
+pub struct MapError {
+    pub kind: MapErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            MapErrorKind::NotFound(e) => write!(f, "can't open map ({})", e),
+            MapErrorKind::InvalidSize => write!(
+                f,
+                "expected 'size width height', from 1 to {}",
+                constantes::MAX_MAP_SIZE
+            ),
+            MapErrorKind::UnknownTile(c) => write!(f, "unknown tile '{}'", c),
+            MapErrorKind::OutOfBounds(c) => write!(f, "tile '{}' is outside the map", c),
+            MapErrorKind::DuplicatePlayer => write!(f, "second player '0', only one allowed"),
+            MapErrorKind::DuplicateExit => write!(f, "second exit '4', only one allowed"),
+            MapErrorKind::TooManyTeleporters => {
+                write!(f, "third teleporter '3', teleporters come in pairs")
+            }
+            MapErrorKind::UnpairedTeleporter => {
+                write!(f, "teleporter '3' without a second teleporter")
+            }
+            MapErrorKind::MissingPlayer => write!(f, "no player '0'"),
+            MapErrorKind::MissingExit => write!(f, "no exit '4'"),
+        }
+    }
+}
+
+impl MapError {
+    fn new(kind: MapErrorKind, line: usize, column: usize) -> Self {
+        MapError { kind, line, column }
+    }
 }
 
 pub fn clear_map(game_state: &mut GameState) {
@@ -57,108 +136,372 @@ pub fn clear_map(game_state: &mut GameState) {
     game_state.clouds.clear();
     game_state.teleporters[0] = None;
     game_state.teleporters[1] = None;
+    game_state.turn = 0;
+    game_state.move_count = 0;
+    grid::rebuild(game_state);
+}
+
+pub fn parse_map(text: &str) -> Result<MapData, MapError> {
+    let mut tiles = vec![];
+    let mut player_count = 0;
+    let mut exit_count = 0;
+    let mut teleporter_positions: Vec<(usize, usize)> = vec![];
+    let mut rows: Vec<&str> = text.lines().collect();
+    let mut first_line = 1;
+    let (width, height) = match rows.first().and_then(|row| row.strip_prefix("size ")) {
+        Some(size) => {
+            let is_valid_size = |n: &i32| *n > 0 && *n <= constantes::MAX_MAP_SIZE;
+            let size: Vec<i32> = size
+                .split_whitespace()
+                .map(|n| n.parse().ok().filter(is_valid_size))
+                .collect::<Option<_>>()
+                .ok_or_else(|| MapError::new(MapErrorKind::InvalidSize, 1, 1))?;
+            match size[..] {
+                [width, height] => {
+                    rows.remove(0);
+                    first_line = 2;
+                    (width, height)
+                }
+                _ => return Err(MapError::new(MapErrorKind::InvalidSize, 1, 1)),
+            }
+        }
+        None => (constantes::DEFAULT_MAP_WIDTH, constantes::DEFAULT_MAP_HEIGHT),
+    };
+    // Empty lines at the end of the file aren't part of the map
+    let row_count = rows.iter().rposition(|row| !row.is_empty()).map_or(0, |i| i + 1);
+    let mut line = first_line;
+    let mut column = 1;
+    for (y, row) in rows[..row_count].iter().enumerate() {
+        line = y + first_line;
+        column = 1;
+        for (x, c) in row.chars().enumerate() {
+            column = x + 1;
+            // Padding past the edge is fine, tiles aren't
+            if c == '-' {
+                continue;
+            }
+            let tile = Tile::from_char(c)
+                .ok_or_else(|| MapError::new(MapErrorKind::UnknownTile(c), line, column))?;
+            if x as i32 >= width || y as i32 >= height {
+                return Err(MapError::new(MapErrorKind::OutOfBounds(c), line, column));
+            }
+            match tile {
+                Tile::Player => {
+                    player_count += 1;
+                    if player_count > 1 {
+                        return Err(MapError::new(MapErrorKind::DuplicatePlayer, line, column));
+                    }
+                }
+                Tile::Exit => {
+                    exit_count += 1;
+                    if exit_count > 1 {
+                        return Err(MapError::new(MapErrorKind::DuplicateExit, line, column));
+                    }
+                }
+                Tile::Teleporter => {
+                    if teleporter_positions.len() >= 2 {
+                        let kind = MapErrorKind::TooManyTeleporters;
+                        return Err(MapError::new(kind, line, column));
+                    }
+                    teleporter_positions.push((line, column));
+                }
+                _ => {}
+            }
+            tiles.push((na::Point2::new(x as i32, y as i32), tile));
+        }
+    }
+
+    // Problems without a tile to point at are reported at the end of the map
+    if player_count == 0 {
+        return Err(MapError::new(MapErrorKind::MissingPlayer, line, column));
+    }
+    if exit_count == 0 {
+        return Err(MapError::new(MapErrorKind::MissingExit, line, column));
+    }
+    if let [(teleporter_line, teleporter_column)] = teleporter_positions[..] {
+        let kind = MapErrorKind::UnpairedTeleporter;
+        return Err(MapError::new(kind, teleporter_line, teleporter_column));
+    }
+    Ok(MapData {
+        width,
+        height,
+        tiles,
+    })
+}
+
+// The text parse_map reads, the size line and every row written out to the full width
+pub fn map_to_string(map_data: &MapData) -> String {
+    let width = map_data.width.max(0) as usize;
+    let height = map_data.height.max(0) as usize;
+    let mut rows = vec![vec!['-'; width]; height];
+    for (position, tile) in map_data.tiles.iter() {
+        if let Some(c) = rows
+            .get_mut(position.y as usize)
+            .and_then(|row| row.get_mut(position.x as usize))
+        {
+            *c = tile.to_char();
+        }
+    }
+    let mut text = format!("size {} {}\n", map_data.width, map_data.height);
+    for row in rows {
+        text.extend(row);
+        text.push('\n');
+    }
+    text
+}
+
+// Reads and parses a map from the resources
+pub fn read_map(ctx: &mut Context, map_name: &str) -> Result<MapData, MapError> {
+    let mut buffer = String::new();
+    ggez::filesystem::open(ctx, map_name)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|mut file| {
+            file.read_to_string(&mut buffer)
+                .map_err(|e| e.to_string())
+        })
+        .map_err(|e| MapError::new(MapErrorKind::NotFound(e), 1, 1))?;
+    parse_map(&buffer)
 }
 
 pub fn load_map(
     ctx: &mut Context,
     game_state: &mut GameState,
-    map_index: usize,
+    map_name: &str,
+    screen_size: &na::Point2<f32>,
+    rng: &mut GameRng,
+) -> Result<(), MapError> {
+    let map_data = read_map(ctx, map_name)?;
+    start_map(game_state, &map_data, screen_size, rng);
+    Ok(())
+}
+
+// Builds a map that is already parsed, with its decoration
+pub fn start_map(
+    game_state: &mut GameState,
+    map_data: &MapData,
     screen_size: &na::Point2<f32>,
+    rng: &mut GameRng,
 ) {
-    let map_filename = get_map_name(map_index);
-    let mut file = ggez::filesystem::open(ctx, map_filename).expect("no map file");
-    let mut buffer = String::new();
-    file.read_to_string(&mut buffer).unwrap();
-    let mut x: i32 = 0;
-    let mut y: i32 = 0;
-    for char in buffer.chars() {
-        // almost every case have a transform, create here to avoid redundant code
+    build_map(game_state, map_data);
+
+    // visual position starts at 0,0
+    util::force_visual_positions(game_state, screen_size);
+
+    // Clouds generation
+    cloud::spawn(game_state, rng);
+
+    foilage::generate(game_state, rng);
+}
+
+// Creates the map entities, without any decoration (clouds, foilage)
+// Doesn't need a Context, so headless tools can load maps too
+pub fn build_map(game_state: &mut GameState, map_data: &MapData) {
+    game_state.map_size = na::Point2::new(map_data.width, map_data.height);
+    for (position, tile) in map_data.tiles.iter() {
         let transform = TransformComponent {
-            position: na::Point2::new(x, y),
+            position: *position,
         };
 
-        match char {
-            '0' => {
+        match tile {
+            Tile::Player => {
                 game_state.player = Player {
                     transform,
                     is_alive: true,
                     ..Default::default()
                 };
             }
-            '1' => {
+            Tile::Grass => {
                 game_state.grasses.push(Grass {
                     transform,
                     sprite: SpriteComponent {
-                        texture_index: 1,
+                        texture: tile.texture(),
                         ..Default::default()
                     },
                     ..Default::default()
                 });
             }
-            '2' => {
+            Tile::SkeletonBlock => {
                 game_state.skeleton_blocks.push(SkeletonBlock {
                     transform,
                     sprite: SpriteComponent {
-                        texture_index: 3,
+                        texture: tile.texture(),
                         ..Default::default()
                     },
                     ..Default::default()
                 });
             }
-            '3' => {
+            Tile::Teleporter => {
                 let sprite = SpriteComponent {
-                    texture_index: 5,
+                    texture: tile.texture(),
                     ..Default::default()
                 };
-                if game_state.teleporters[0].is_none() {
-                    game_state.teleporters[0] = Some(Teleporter {
-                        transform,
-                        sprite,
-                        ..Default::default()
-                    });
-                } else {
-                    game_state.teleporters[1] = Some(Teleporter {
-                        transform,
-                        sprite,
-                        ..Default::default()
-                    });
-                }
-                
+                let index = match game_state.teleporters[0] {
+                    None => 0,
+                    Some(_) => 1,
+                };
+                game_state.teleporters[index] = Some(Teleporter {
+                    transform,
+                    sprite,
+                    ..Default::default()
+                });
             }
-            '4' => {
+            Tile::Exit => {
                 game_state.exit = Exit {
                     transform,
                     sprite: SpriteComponent {
-                        texture_index: 6,
+                        texture: tile.texture(),
                         ..Default::default()
                     },
                     ..Default::default()
                 };
             }
-            '5' => {
+            Tile::Dirt => {
                 game_state.grasses.push(Grass {
                     transform,
                     sprite: SpriteComponent {
-                        texture_index: 10,
+                        texture: tile.texture(),
                         ..Default::default()
                     },
                     ..Default::default()
                 });
             }
-            '\n' => {
-                // -1 because it will increment right after to 0
-                x = -1;
-                y += 1;
-            }
-            _ => {}
         }
-        x += 1;
     }
-    // visual position starts at 0,0
-    util::force_visual_positions(game_state, screen_size);
+    grid::rebuild(game_state);
+}
 
-    // Clouds generation
-    cloud::spawn(game_state);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> MapError {
+        match parse_map(text) {
+            Ok(_) => panic!("expected an error for {:?}", text),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn unknown_tile() {
+        let e = error("0-4\n1x11\n");
+        assert!(matches!(e.kind, MapErrorKind::UnknownTile('x')));
+        assert_eq!((e.line, e.column), (2, 2));
+    }
 
-    foilage::generate(game_state);
+    #[test]
+    fn second_player() {
+        let e = error("0-4\n--0\n");
+        assert!(matches!(e.kind, MapErrorKind::DuplicatePlayer));
+        assert_eq!((e.line, e.column), (2, 3));
+    }
+
+    #[test]
+    fn second_exit() {
+        let e = error("0-4\n111\n4\n");
+        assert!(matches!(e.kind, MapErrorKind::DuplicateExit));
+        assert_eq!((e.line, e.column), (3, 1));
+    }
+
+    #[test]
+    fn third_teleporter() {
+        let e = error("0-4-3\n3---3\n");
+        assert!(matches!(e.kind, MapErrorKind::TooManyTeleporters));
+        assert_eq!((e.line, e.column), (2, 5));
+    }
+
+    #[test]
+    fn unpaired_teleporter_points_at_the_teleporter() {
+        let e = error("0-4\n-3-\n111\n");
+        assert!(matches!(e.kind, MapErrorKind::UnpairedTeleporter));
+        assert_eq!((e.line, e.column), (2, 2));
+    }
+
+    #[test]
+    fn missing_player_or_exit_points_at_the_end_of_the_map() {
+        let e = error("--4\n111\n");
+        assert!(matches!(e.kind, MapErrorKind::MissingPlayer));
+        assert_eq!((e.line, e.column), (2, 3));
+
+        let e = error("0\n1111\n\n");
+        assert!(matches!(e.kind, MapErrorKind::MissingExit));
+        assert_eq!((e.line, e.column), (2, 4));
+    }
+
+    #[test]
+    fn valid_map_with_teleporters() {
+        let map_data = parse_map("0-4-3\n3\n11111\n").unwrap();
+        let teleporters = map_data
+            .tiles
+            .iter()
+            .filter(|(_, tile)| *tile == Tile::Teleporter)
+            .count();
+        assert_eq!(teleporters, 2);
+    }
+
+    #[test]
+    fn campaign_map_keeps_the_default_size() {
+        let map_data = parse_map(include_str!("../resources/maps/map_hard2.txt")).unwrap();
+        assert_eq!((map_data.width, map_data.height), (10, 8));
+    }
+
+    #[test]
+    fn padding_past_the_edge_is_ignored() {
+        let map_data = parse_map("0-4-------------------\n1111111111----\n").unwrap();
+        assert_eq!((map_data.width, map_data.height), (10, 8));
+        assert_eq!(map_data.tiles.len(), 12);
+    }
+
+    #[test]
+    fn tile_past_the_edge_is_out_of_bounds() {
+        let e = error("0-4-------1\n");
+        assert!(matches!(e.kind, MapErrorKind::OutOfBounds('1')));
+        assert_eq!((e.line, e.column), (1, 11));
+
+        let e = error("0-4\n\n\n\n\n\n\n\n1\n");
+        assert!(matches!(e.kind, MapErrorKind::OutOfBounds('1')));
+        assert_eq!((e.line, e.column), (9, 1));
+    }
+
+    #[test]
+    fn size_line_sets_the_size() {
+        let map_data = parse_map("size 12 3\n0----------4\n111111111111\n").unwrap();
+        assert_eq!((map_data.width, map_data.height), (12, 3));
+
+        // Rows count from the line after the size
+        let e = error("size 3 2\n0-4\n111\n1\n");
+        assert!(matches!(e.kind, MapErrorKind::OutOfBounds('1')));
+        assert_eq!((e.line, e.column), (4, 1));
+    }
+
+    #[test]
+    fn invalid_size_line() {
+        for text in &["size 10\n0-4\n", "size 10 0\n0-4\n", "size ten 8\n0-4\n"] {
+            let e = error(text);
+            assert!(matches!(e.kind, MapErrorKind::InvalidSize));
+            assert_eq!((e.line, e.column), (1, 1));
+        }
+    }
+
+    #[test]
+    fn size_is_capped() {
+        for text in &[
+            "size 100000 100000\n0-4\n",
+            "size 65 8\n0-4\n",
+            "size 10 65\n0-4\n",
+        ] {
+            let e = error(text);
+            assert!(matches!(e.kind, MapErrorKind::InvalidSize));
+            assert_eq!((e.line, e.column), (1, 1));
+        }
+        let map_data = parse_map("size 64 64\n0-4\n111\n").unwrap();
+        assert_eq!((map_data.width, map_data.height), (64, 64));
+    }
+
+    #[test]
+    fn map_to_string_reads_back_the_same() {
+        let map_data = parse_map("size 4 3\n0--4\n-33-\n1111\n").unwrap();
+        let read_back = parse_map(&map_to_string(&map_data)).unwrap();
+        assert_eq!((read_back.width, read_back.height), (4, 3));
+        assert_eq!(read_back.tiles, map_data.tiles);
+    }
 }
@@ -1,13 +1,14 @@
+use crate::game_rng::GameRng;
 use crate::transform_compontent::TransformComponent;
 use ggez::graphics::spritebatch::SpriteBatch;
-use ggez::graphics::{DrawParam, Image};
+use ggez::graphics::{DrawParam, Image, Rect};
 use ggez::Context;
 use ggez::GameResult;
 use gwg as ggez;
-use gwg::rand;
 use nalgebra as na;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::str::FromStr;
 
 static DEFAULT_CAPACITY: usize = 8;
 static PI: f32 = std::f32::consts::PI;
@@ -16,9 +17,6 @@ static TAU: f32 = PI * 2.0;
 use na::Point2;
 use na::Vector2;
 
-// Todo, since start_lifetime can be randomized, the scaling will not start at start_scale
-// because the lifetime fraction also then is randomized
-
 // helper funcitons
 // in radians
 fn vec_from_angle(angle: f32) -> na::Vector2<f32> {
@@ -42,8 +40,12 @@ pub struct ParticleSystem {
     angular_velocities: Vec<f32>,
     scales: Vec<f32>,
     rotations: Vec<f32>,
-    lifetimes: Vec<f32>,
+    lifetimes: Vec<f32>,       // seconds left
+    start_lifetimes: Vec<f32>, // seconds the particle lives in total
+    ages: Vec<f32>,            // 0 when spawned, 1 when it dies
+    start_scales: Vec<f32>,
     colors: Vec<ggez::graphics::Color>,
+    start_colors: Vec<ggez::graphics::Color>,
     particle_indexes: VecDeque<usize>,
     available_indexes: VecDeque<usize>,
 
@@ -61,10 +63,13 @@ pub struct ParticleSystem {
     pub start_scale: ValueGetter<f32>,
     pub start_angular_velocity: ValueGetter<f32>,
     pub start_color: ValueGetter<ggez::graphics::Color>,
-    pub end_scale: f32,
+    pub color_over_life: Gradient, // tints the start color
+    pub scale_over_life: Curve,    // multiplies the start scale
+    pub speed_over_life: Curve,    // multiplies the velocity
 
     sprite_batch_dirty: bool,
     pub sprite_batch: SpriteBatch,
+    pub src: Rect, // part of the image particles are drawn with, for sprites in an atlas
 }
 
 fn make_image(ctx: &mut Context) -> Image {
@@ -74,20 +79,21 @@ fn make_image(ctx: &mut Context) -> Image {
 }
 
 impl ParticleSystem {
-    pub fn copy_settings(&mut self, other: &ParticleSystem) {
-        self.emit_shape = other.emit_shape;
-        self.velocity_type = other.velocity_type;
-        self.gravity = other.gravity;
-        self.transform_space = other.transform_space;
-        self.scale = other.scale;
-        self.position = other.position;
-        self.start_lifetime = other.start_lifetime;
-        self.start_speed = other.start_speed;
-        self.start_rotation = other.start_rotation;
-        self.start_scale = other.start_scale;
-        self.start_angular_velocity = other.start_angular_velocity;
-        self.start_color = other.start_color;
-        self.end_scale = other.end_scale;
+    // Everything but the position and scale, which the game sets before emitting
+    pub fn apply_preset(&mut self, preset: &ParticlePreset) {
+        self.emit_shape = preset.emit_shape;
+        self.velocity_type = preset.velocity_type;
+        self.gravity = preset.gravity;
+        self.transform_space = preset.transform_space;
+        self.start_lifetime = preset.start_lifetime;
+        self.start_speed = preset.start_speed;
+        self.start_rotation = preset.start_rotation;
+        self.start_scale = preset.start_scale;
+        self.start_angular_velocity = preset.start_angular_velocity;
+        self.start_color = preset.start_color;
+        self.color_over_life = preset.color_over_life.clone();
+        self.scale_over_life = preset.scale_over_life.clone();
+        self.speed_over_life = preset.speed_over_life.clone();
     }
 
     pub fn new(ctx: &mut Context, image_option: Option<Image>) -> Self {
@@ -105,38 +111,42 @@ impl ParticleSystem {
 
         let sprite_batch = SpriteBatch::new(final_image);
 
+        let preset = ParticlePreset::default();
         let mut particle_system = ParticleSystem {
             positions: Vec::with_capacity(DEFAULT_CAPACITY),
             velocities: Vec::with_capacity(DEFAULT_CAPACITY),
             scales: Vec::with_capacity(DEFAULT_CAPACITY),
             lifetimes: Vec::with_capacity(DEFAULT_CAPACITY),
+            start_lifetimes: Vec::with_capacity(DEFAULT_CAPACITY),
+            ages: Vec::with_capacity(DEFAULT_CAPACITY),
+            start_scales: Vec::with_capacity(DEFAULT_CAPACITY),
             rotations: Vec::with_capacity(DEFAULT_CAPACITY),
             colors: Vec::with_capacity(DEFAULT_CAPACITY),
+            start_colors: Vec::with_capacity(DEFAULT_CAPACITY),
             angular_velocities: Vec::with_capacity(DEFAULT_CAPACITY),
             particle_indexes: VecDeque::with_capacity(DEFAULT_CAPACITY),
             available_indexes,
 
-            emit_shape: EmitShape::Point,
-            velocity_type: VelocityType::Angle(AngleData::new(PI, Some(0.5))),
-            gravity: -9.0,
-            transform_space: TransformSpace::World,
+            emit_shape: preset.emit_shape,
+            velocity_type: preset.velocity_type,
+            gravity: preset.gravity,
+            transform_space: preset.transform_space,
 
             scale: 1.0,
             position: Vector2::new(200.0, 300.0),
-            start_lifetime: ValueGetter::Single(1.4),
-            start_speed: ValueGetter::Range(0.0, 3.0),
-            start_rotation: ValueGetter::Single(0.0),
-            start_scale: ValueGetter::Single(16.0),
-            start_angular_velocity: ValueGetter::Range(-1.0, 1.0),
-            start_color: ValueGetter::Range(
-                ggez::graphics::Color::new(0.5, 0.2, 0.2, 1.0),
-                ggez::graphics::Color::new(1.0, 1.0, 0.2, 1.0),
-            ),
-
-            end_scale: 0.0,
+            start_lifetime: preset.start_lifetime,
+            start_speed: preset.start_speed,
+            start_rotation: preset.start_rotation,
+            start_scale: preset.start_scale,
+            start_angular_velocity: preset.start_angular_velocity,
+            start_color: preset.start_color,
+            color_over_life: preset.color_over_life,
+            scale_over_life: preset.scale_over_life,
+            speed_over_life: preset.speed_over_life,
 
             sprite_batch_dirty: true,
             sprite_batch,
+            src: Rect::one(),
         };
         let available_indexes = particle_system.available_indexes.len();
         for _i in 0..available_indexes {
@@ -151,6 +161,15 @@ impl ParticleSystem {
         for _i in 0..available_indexes {
             particle_system.lifetimes.push(0.0);
         }
+        for _i in 0..available_indexes {
+            particle_system.start_lifetimes.push(0.0);
+        }
+        for _i in 0..available_indexes {
+            particle_system.ages.push(0.0);
+        }
+        for _i in 0..available_indexes {
+            particle_system.start_scales.push(1.0);
+        }
         for _i in 0..available_indexes {
             particle_system.rotations.push(0.0);
         }
@@ -160,6 +179,9 @@ impl ParticleSystem {
         for _i in 0..available_indexes {
             particle_system.colors.push(ggez::graphics::WHITE);
         }
+        for _i in 0..available_indexes {
+            particle_system.start_colors.push(ggez::graphics::WHITE);
+        }
         particle_system
     }
 
@@ -180,6 +202,7 @@ impl ParticleSystem {
             }
 
             let drawparam = DrawParam {
+                src: self.src,
                 offset: Point2::new(0.5, 0.5).into(),
                 dest: (dest * self.scale).into(),
                 scale: mint::Vector2 {
@@ -215,18 +238,24 @@ impl ParticleSystem {
             true
         });
 
+        // Curves go by the age of each particle, so a random lifetime stretches them
+        // instead of cutting them short
         for i in self.particle_indexes.iter() {
+            let age = match self.start_lifetimes[*i] > 0.0 {
+                true => 1.0 - self.lifetimes[*i] / self.start_lifetimes[*i],
+                false => 1.0,
+            };
+            self.ages[*i] = age;
             self.velocities[*i].y -= self.gravity * dt;
-            self.positions[*i] += self.velocities[*i];
+            self.positions[*i] += self.velocities[*i] * self.speed_over_life.get(age);
             self.rotations[*i] += self.angular_velocities[*i] * dt;
-            let normalized_life =
-                (self.start_lifetime.max() - lifetimes[*i]) / self.start_lifetime.max();
-            self.scales[*i] = lerp(self.start_scale.max(), self.end_scale, normalized_life);
+            self.scales[*i] = self.start_scales[*i] * self.scale_over_life.get(age);
+            self.colors[*i] = tint(self.start_colors[*i], self.color_over_life.get(age));
         }
         self.sprite_batch_dirty = true;
     }
 
-    pub fn emit(&mut self, amount: i32) {
+    pub fn emit(&mut self, amount: i32, rng: &mut GameRng) {
         let mut amount = amount;
         //for i in 0..amount {
         while amount > 0 {
@@ -234,7 +263,7 @@ impl ParticleSystem {
             match index_option {
                 Some(index) => {
                     // make unused particle come alive
-                    self.particle_setup(index);
+                    self.particle_setup(index, rng);
                 }
                 None => {
                     // Resize vectors and spawn a new particle
@@ -251,6 +280,10 @@ impl ParticleSystem {
     // Returns the first available index
     fn grow(&mut self, additional: usize) {
         self.lifetimes.reserve(additional);
+        self.start_lifetimes.reserve(additional);
+        self.ages.reserve(additional);
+        self.start_scales.reserve(additional);
+        self.start_colors.reserve(additional);
         self.positions.reserve(additional);
         self.velocities.reserve(additional);
         self.rotations.reserve(additional);
@@ -274,6 +307,15 @@ impl ParticleSystem {
         for _i in self.lifetimes.len()..self.lifetimes.capacity() {
             self.lifetimes.push(0.0);
         }
+        for _i in self.start_lifetimes.len()..self.start_lifetimes.capacity() {
+            self.start_lifetimes.push(0.0);
+        }
+        for _i in self.ages.len()..self.ages.capacity() {
+            self.ages.push(0.0);
+        }
+        for _i in self.start_scales.len()..self.start_scales.capacity() {
+            self.start_scales.push(0.0);
+        }
         for _i in self.rotations.len()..self.rotations.capacity() {
             self.rotations.push(0.0);
         }
@@ -283,6 +325,9 @@ impl ParticleSystem {
         for _i in self.colors.len()..self.colors.capacity() {
             self.colors.push(ggez::graphics::WHITE);
         }
+        for _i in self.start_colors.len()..self.start_colors.capacity() {
+            self.start_colors.push(ggez::graphics::WHITE);
+        }
 
         let newly_added = self.lifetimes.len() - next_available_index;
         for i in 0..newly_added {
@@ -292,69 +337,100 @@ impl ParticleSystem {
 
     // Setup the data for a newly created particle
     // index is assumed to be in bounds
-    fn particle_setup(&mut self, index: usize) {
-        let mut pos = self.emit_shape.get_position();
+    fn particle_setup(&mut self, index: usize, rng: &mut GameRng) {
+        let mut pos = self.emit_shape.get_position(rng);
+        let direction = self
+            .emit_shape
+            .get_direction(&self.velocity_type, &pos, rng);
         if let TransformSpace::World = self.transform_space {
             pos += self.position;
         }
-        let rotation = self.start_rotation.get();
-        let angular_velocity = self.start_angular_velocity.get();
-        let scale = self.start_scale.get();
-        let speed = self.start_speed.get();
-        let direction = self.emit_shape.get_direction(&self.velocity_type, &pos);
+        let rotation = self.start_rotation.get(rng);
+        let angular_velocity = self.start_angular_velocity.get(rng);
+        let scale = self.start_scale.get(rng);
+        let speed = self.start_speed.get(rng);
         let velocity = direction * speed;
-        let lifetime = self.start_lifetime.get();
-        let color = self.start_color.get();
+        let lifetime = self.start_lifetime.get(rng);
+        let color = self.start_color.get(rng);
 
         self.lifetimes[index] = lifetime;
+        self.start_lifetimes[index] = lifetime;
+        self.ages[index] = 0.0;
         self.positions[index] = pos;
         self.velocities[index] = velocity;
         self.rotations[index] = rotation;
-        self.scales[index] = scale;
+        self.start_scales[index] = scale;
+        self.scales[index] = scale * self.scale_over_life.get(0.0);
         self.angular_velocities[index] = angular_velocity;
-        self.colors[index] = color;
+        self.start_colors[index] = color;
+        self.colors[index] = tint(color, self.color_over_life.get(0.0));
         self.particle_indexes.push_back(index);
     }
 }
 
 #[derive(Clone, Copy)]
 pub enum EmitShape {
-    Point, // The position of the particle system
-    //Line(Vector2<f32>),
-    //Rect(RectData),
-    //Cone(ConeData),
+    Point,          // The position of the particle system
+    Line(LineData), // centered on the position
+    Rect(RectData), // centered on the position
+    Cone(ConeData), // a slice of a circle
     Circle(CircleData),
 }
 
 #[derive(Clone, Copy)]
-struct RectData {
-    size: Vector2<f32>,
-    spawn_type: SpawnType,
+pub struct LineData {
+    pub vector: Vector2<f32>,  // from one end to the other
+    pub spawn_type: SpawnType, // edge spawns at the two ends
 }
 
 #[derive(Clone, Copy)]
-struct ConeData {
-    radius: f32,
-    angle: f32,
-    spawn_type: SpawnType,
+pub struct RectData {
+    pub size: Vector2<f32>,
+    pub spawn_type: SpawnType,
 }
 
 #[derive(Clone, Copy)]
-struct CircleData {
-    radius: f32,
-    spawn_type: SpawnType,
+pub struct ConeData {
+    pub radius: f32,
+    pub direction: f32, // in radians, like velocity angles
+    pub angle: f32,     // full width of the slice, in radians
+    pub spawn_type: SpawnType,
 }
 
 #[derive(Clone, Copy)]
-enum SpawnType {
+pub struct CircleData {
+    pub radius: f32,
+    pub spawn_type: SpawnType,
+}
+
+#[derive(Clone, Copy)]
+pub enum SpawnType {
     Volume,
     Edge,
 }
 
+impl SpawnType {
+    pub fn from_name(name: &str) -> Option<SpawnType> {
+        match name {
+            "volume" => Some(SpawnType::Volume),
+            "edge" => Some(SpawnType::Edge),
+            _ => None,
+        }
+    }
+
+    // Distance from the center of a round shape, volume spreads evenly over the area
+    fn distance(self, radius: f32, rng: &mut GameRng) -> f32 {
+        match self {
+            SpawnType::Volume => radius * rng.gen_range(0.0f32, 1.0).sqrt(),
+            SpawnType::Edge => radius,
+        }
+    }
+}
+
 // decides how velocity should be calculated
 #[derive(Clone, Copy)]
 pub enum VelocityType {
-    //AlignToDirection(AlignToDirectionData),
+    AlignToDirection(AlignToDirectionData), // away from the center of the shape
     Angle(AngleData),
     Random,
 }
@@ -376,72 +452,191 @@ impl AngleData {
     }
 }
 
+fn random_delta(max_delta: Option<f32>, rng: &mut GameRng) -> f32 {
+    match max_delta {
+        Some(d) => rng.gen_range(-d, d),
+        None => 0.0,
+    }
+}
+
 impl EmitShape {
-    // Todo: Implement other shapes other than point and cirle
-    pub fn get_position(&self) -> Point2<f32> {
+    // Relative to the position of the system
+    pub fn get_position(&self, rng: &mut GameRng) -> Point2<f32> {
         match self {
             EmitShape::Point => Point2::new(0.0, 0.0),
-            EmitShape::Circle(c) => {
-                let mut dir = vec_from_angle(TAU);
-                if let SpawnType::Volume = c.spawn_type {
-                    dir *= rand::gen_range(0.0, 1.0);
+            EmitShape::Line(l) => {
+                let t = match l.spawn_type {
+                    SpawnType::Volume => rng.gen_range(-0.5, 0.5),
+                    SpawnType::Edge if rng.gen_range(0, 2) == 0 => -0.5,
+                    SpawnType::Edge => 0.5,
+                };
+                Point2::from(l.vector * t)
+            }
+            EmitShape::Rect(r) => {
+                let (w, h) = (r.size.x, r.size.y);
+                match r.spawn_type {
+                    SpawnType::Volume => {
+                        Point2::new(rng.gen_range(-0.5, 0.5) * w, rng.gen_range(-0.5, 0.5) * h)
+                    }
+                    SpawnType::Edge => {
+                        // walk clockwise around the outline from the top left corner
+                        let p = rng.gen_range(0.0, 2.0 * (w + h));
+                        let (x, y) = if p < w {
+                            (p, 0.0)
+                        } else if p < w + h {
+                            (w, p - w)
+                        } else if p < 2.0 * w + h {
+                            (2.0 * w + h - p, h)
+                        } else {
+                            (0.0, 2.0 * (w + h) - p)
+                        };
+                        Point2::new(x - w * 0.5, y - h * 0.5)
+                    }
                 }
-                dir.into()
             }
-            //EmitShape::Line(v) => {Point2{x: 0.0, y: 0.0}},
-            //EmitShape::Rect(r) => {Point2{x: 0.0, y: 0.0}},
-            //EmitShape::Cone(c, a) => {Point2{x: 0.0, y: 0.0}},
+            EmitShape::Cone(c) => {
+                let half_angle = c.angle * 0.5;
+                let angle = c.direction + rng.gen_range(-half_angle, half_angle);
+                Point2::from(vec_from_angle(angle) * c.spawn_type.distance(c.radius, rng))
+            }
+            EmitShape::Circle(c) => {
+                let angle = rng.gen_range(0.0, TAU);
+                Point2::from(vec_from_angle(angle) * c.spawn_type.distance(c.radius, rng))
+            }
         }
     }
 
+    // position is relative to the system, as get_position returns it
     pub fn get_direction(
         &self,
         velocity_type: &VelocityType,
-        _position: &Point2<f32>,
+        position: &Point2<f32>,
+        rng: &mut GameRng,
     ) -> Vector2<f32> {
         match velocity_type {
-            VelocityType::Random => vec_from_angle(rand::gen_range(0.0, TAU)),
-            VelocityType::Angle(a) => {
-                let delta = match a.max_delta {
-                    Some(d) => rand::gen_range(-d, d),
-                    None => 0.0,
+            VelocityType::Random => vec_from_angle(rng.gen_range(0.0, TAU)),
+            VelocityType::Angle(a) => vec_from_angle(a.angle + random_delta(a.max_delta, rng)),
+            VelocityType::AlignToDirection(a) => {
+                let outward = match self {
+                    // a line has no center to move away from, rightward lines push up
+                    EmitShape::Line(l) => Vector2::new(l.vector.y, -l.vector.x),
+                    _ => position.coords,
+                };
+                let angle = match outward.norm() > f32::EPSILON {
+                    true => outward.x.atan2(outward.y),
+                    false => rng.gen_range(0.0, TAU), // spawned on the center, any way is out
                 };
-                vec_from_angle(a.angle + delta)
+                vec_from_angle(angle + random_delta(a.max_delta, rng))
             }
-            // VelocityType::AlignToDirection(a) => {
-            //     match self {
-            //         EmitShape::Point => {
-            //             vec_from_angle(TAU);
-            //         },
-            //         EmitShape::Circle(c) => {
-            //             // From 0.0 out to where it will spawn
+        }
+    }
+}
+
+// Values over the lifetime of a particle, keys are (age, value) with ages from 0 to 1.
+// Between keys the value is interpolated, before the first and after the last it holds
+#[derive(Clone)]
+pub struct Curve {
+    keys: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    pub fn constant(value: f32) -> Self {
+        Curve {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    // None without keys, the keys don't need to be in order
+    pub fn new(mut keys: Vec<(f32, f32)>) -> Option<Self> {
+        if keys.is_empty() {
+            return None;
+        }
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Some(Curve { keys })
+    }
+
+    pub fn get(&self, age: f32) -> f32 {
+        let (index, delta) = key_position(&self.keys, age);
+        match index + 1 < self.keys.len() {
+            true => lerp(self.keys[index].1, self.keys[index + 1].1, delta),
+            false => self.keys[index].1,
+        }
+    }
+}
 
-            //         },
+// Colors over the lifetime of a particle, like Curve but for color and alpha
+#[derive(Clone)]
+pub struct Gradient {
+    keys: Vec<(f32, ggez::graphics::Color)>,
+}
+
+impl Gradient {
+    pub fn constant(color: ggez::graphics::Color) -> Self {
+        Gradient {
+            keys: vec![(0.0, color)],
+        }
+    }
+
+    pub fn new(mut keys: Vec<(f32, ggez::graphics::Color)>) -> Option<Self> {
+        if keys.is_empty() {
+            return None;
+        }
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Some(Gradient { keys })
+    }
+
+    pub fn get(&self, age: f32) -> ggez::graphics::Color {
+        let (index, delta) = key_position(&self.keys, age);
+        let from = self.keys[index].1;
+        let to = match self.keys.get(index + 1) {
+            Some(key) => key.1,
+            None => return from,
+        };
+        ggez::graphics::Color::new(
+            lerp(from.r, to.r, delta),
+            lerp(from.g, to.g, delta),
+            lerp(from.b, to.b, delta),
+            lerp(from.a, to.a, delta),
+        )
+    }
+}
 
-            //     }
-            // },
+// Index of the key at or before the age and how far the age is towards the next key
+fn key_position<T>(keys: &[(f32, T)], age: f32) -> (usize, f32) {
+    let index = keys.iter().rposition(|key| key.0 <= age).unwrap_or(0);
+    match keys.get(index + 1) {
+        Some(next) if age > keys[index].0 => {
+            (index, (age - keys[index].0) / (next.0 - keys[index].0))
         }
+        _ => (index, 0.0),
     }
 }
 
+fn tint(color: ggez::graphics::Color, tint: ggez::graphics::Color) -> ggez::graphics::Color {
+    ggez::graphics::Color::new(
+        color.r * tint.r,
+        color.g * tint.g,
+        color.b * tint.b,
+        color.a * tint.a,
+    )
+}
+
 #[derive(Clone, Copy)]
 pub enum ValueGetter<T> {
     Single(T),
     Range(T, T),
 }
 
-// Todo: Implement range, randomization
 impl ValueGetter<ggez::graphics::Color> {
-    pub fn get(&self) -> ggez::graphics::Color {
+    pub fn get(&self, rng: &mut GameRng) -> ggez::graphics::Color {
         match *self {
             ValueGetter::Single(v) => v,
             ValueGetter::Range(v1, v2) => {
                 let (low_r, low_g, low_b) = v1.into();
                 let (high_r, high_g, high_b) = v2.into();
-                // gen_range doesn't support u8 in good-web-easy
-                let r = rand::gen_range(low_r as i32, high_r as i32) as u8;
-                let g = rand::gen_range(low_g as i32, high_g as i32) as u8;
-                let b = rand::gen_range(low_b as i32, high_b as i32) as u8;
+                let r = rng.gen_range(low_r, high_r);
+                let g = rng.gen_range(low_g, high_g);
+                let b = rng.gen_range(low_b, high_b);
                 (r, g, b).into()
             }
         }
@@ -449,10 +644,10 @@ impl ValueGetter<ggez::graphics::Color> {
 }
 
 impl ValueGetter<f32> {
-    pub fn get(&self) -> f32 {
+    pub fn get(&self, rng: &mut GameRng) -> f32 {
         match *self {
             ValueGetter::Single(v) => v,
-            ValueGetter::Range(v1, v2) => rand::gen_range(v1, v2),
+            ValueGetter::Range(v1, v2) => rng.gen_range(v1, v2),
         }
     }
     pub fn max(&self) -> f32 {
@@ -497,9 +692,9 @@ impl ParticleSystemCollection {
     }
 
     // returns if system is still valid
-    pub fn emit(&mut self, system_identifier: u32, amount: i32) -> bool {
+    pub fn emit(&mut self, system_identifier: u32, amount: i32, rng: &mut GameRng) -> bool {
         if let Some(system) = self.particle_systems.get_mut(&system_identifier) {
-            system.emit(amount);
+            system.emit(amount, rng);
             return true;
         }
         false
@@ -513,30 +708,376 @@ impl ParticleSystemCollection {
     }
 }
 
-pub fn emit_step_particle(
-    particle_collection: &mut ParticleSystemCollection,
-    step_id: &u32,
-    amount: i32,
-    is_right_dir: bool,
-    position: &na::Point2<f32>,
-    screen_size: &na::Point2<f32>,
-) {
-    let step_particle = particle_collection.get_mut(*step_id).unwrap();
-    let mut pos_particle = na::Vector2::new(
-        position.x as f32 / screen_size.x * 16.0,
-        position.y as f32 / screen_size.x * 16.0,
-    );
-
-    if is_right_dir {
-        step_particle.velocity_type = VelocityType::Angle(AngleData::new(-PI * 0.8, Some(0.2)));
-    } else {
-        step_particle.velocity_type = VelocityType::Angle(AngleData::new(PI * 0.8, Some(0.2)));
-    }
-    step_particle.scale = screen_size.x / 16.0;
-    // offset to under player
-    pos_particle.x += 16.0 * 0.5;
-    pos_particle.y += 16.0;
-    step_particle.position = pos_particle;
-
-    step_particle.emit(amount);
+// The look of a particle effect, read from a text file so it can be tuned without touching the code
+// One setting per line, one number is a fixed value and two numbers a random range.
+// Angles are in degrees, 0 points down and 180 up. Colors are 0-255
+//
+// shape point | line x y | rect width height | circle radius | cone radius direction angle,
+// all but point end with volume or edge. velocity random | angle a [delta] | align [delta],
+// align flies away from the center of the shape, or up from a line drawn left to right
+//
+// Over the lifetime of a particle, age goes from 0 to 1 whatever its lifetime is.
+// scale_over_life and speed_over_life are age value pairs multiplying the start scale and speed,
+// color_over_life is age r g b a groups tinting the start color, with 0 alpha fully faded
+//
+// # Grass and dirt thrown up when the player digs
+// amount 20
+// lifetime 0.4 0.5
+// speed 2 3
+// color 82 166 32 66 54 39
+// velocity angle 180 23
+// scale_over_life 0 1 1 0
+#[derive(Clone)]
+pub struct ParticlePreset {
+    pub amount: i32,            // particles per emit
+    pub sprite: Option<String>, // atlas group drawn, one system per sprite in it. None is a plain square
+    pub emit_shape: EmitShape,
+    pub velocity_type: VelocityType,
+    pub gravity: f32,
+    pub transform_space: TransformSpace,
+    pub start_lifetime: ValueGetter<f32>,
+    pub start_speed: ValueGetter<f32>,
+    pub start_rotation: ValueGetter<f32>,
+    pub start_scale: ValueGetter<f32>,
+    pub start_angular_velocity: ValueGetter<f32>,
+    pub start_color: ValueGetter<ggez::graphics::Color>,
+    pub color_over_life: Gradient,
+    pub scale_over_life: Curve,
+    pub speed_over_life: Curve,
+}
+
+impl Default for ParticlePreset {
+    fn default() -> Self {
+        ParticlePreset {
+            amount: 1,
+            sprite: None,
+            emit_shape: EmitShape::Point,
+            velocity_type: VelocityType::Angle(AngleData::new(PI, Some(0.5))),
+            gravity: -9.0,
+            transform_space: TransformSpace::World,
+            start_lifetime: ValueGetter::Single(1.4),
+            start_speed: ValueGetter::Range(0.0, 3.0),
+            start_rotation: ValueGetter::Single(0.0),
+            start_scale: ValueGetter::Single(16.0),
+            start_angular_velocity: ValueGetter::Range(-1.0, 1.0),
+            start_color: ValueGetter::Range(
+                ggez::graphics::Color::new(0.5, 0.2, 0.2, 1.0),
+                ggez::graphics::Color::new(1.0, 1.0, 0.2, 1.0),
+            ),
+            color_over_life: Gradient::constant(ggez::graphics::WHITE),
+            scale_over_life: Curve {
+                keys: vec![(0.0, 1.0), (1.0, 0.0)],
+            },
+            speed_over_life: Curve::constant(1.0),
+        }
+    }
+}
+
+fn parse_numbers(words: &[&str]) -> Option<Vec<f32>> {
+    words.iter().map(|word| word.parse::<f32>().ok()).collect()
+}
+
+fn parse_value(words: &[&str]) -> Option<ValueGetter<f32>> {
+    match parse_numbers(words)?.as_slice() {
+        [value] => Some(ValueGetter::Single(*value)),
+        [low, high] => Some(ValueGetter::Range(*low, *high)),
+        _ => None,
+    }
+}
+
+fn parse_color(words: &[&str]) -> Option<ValueGetter<ggez::graphics::Color>> {
+    let color = |rgb: &[f32]| {
+        ggez::graphics::Color::new(rgb[0] / 255.0, rgb[1] / 255.0, rgb[2] / 255.0, 1.0)
+    };
+    let numbers = parse_numbers(words)?;
+    match numbers.len() {
+        3 => Some(ValueGetter::Single(color(&numbers))),
+        6 => Some(ValueGetter::Range(
+            color(&numbers[..3]),
+            color(&numbers[3..]),
+        )),
+        _ => None,
+    }
+}
+
+fn parse_curve(words: &[&str]) -> Option<Curve> {
+    let numbers = parse_numbers(words)?;
+    if numbers.len() % 2 != 0 {
+        return None;
+    }
+    Curve::new(numbers.chunks(2).map(|key| (key[0], key[1])).collect())
+}
+
+fn parse_gradient(words: &[&str]) -> Option<Gradient> {
+    let numbers = parse_numbers(words)?;
+    if numbers.len() % 5 != 0 {
+        return None;
+    }
+    let keys = numbers.chunks(5).map(|key| {
+        let color = ggez::graphics::Color::new(
+            key[1] / 255.0,
+            key[2] / 255.0,
+            key[3] / 255.0,
+            key[4] / 255.0,
+        );
+        (key[0], color)
+    });
+    Gradient::new(keys.collect())
+}
+
+fn parse_velocity(words: &[&str]) -> Option<VelocityType> {
+    match words {
+        ["random"] => Some(VelocityType::Random),
+        ["align", rest @ ..] => {
+            let max_delta = match parse_numbers(rest)?.as_slice() {
+                [] => None,
+                [delta] => Some(delta.to_radians()),
+                _ => return None,
+            };
+            Some(VelocityType::AlignToDirection(AlignToDirectionData {
+                max_delta,
+            }))
+        }
+        ["angle", rest @ ..] => {
+            let numbers = parse_numbers(rest)?;
+            let max_delta = match numbers.len() {
+                1 => None,
+                2 => Some(numbers[1].to_radians()),
+                _ => return None,
+            };
+            Some(VelocityType::Angle(AngleData::new(
+                numbers[0].to_radians(),
+                max_delta,
+            )))
+        }
+        _ => None,
+    }
+}
+
+fn parse_shape(words: &[&str]) -> Option<EmitShape> {
+    if let ["point"] = words {
+        return Some(EmitShape::Point);
+    }
+    let (spawn_type, rest) = words.split_last()?;
+    let (name, numbers) = rest.split_first()?;
+    let spawn_type = SpawnType::from_name(spawn_type)?;
+    match (*name, parse_numbers(numbers)?.as_slice()) {
+        ("line", [x, y]) => Some(EmitShape::Line(LineData {
+            vector: Vector2::new(*x, *y),
+            spawn_type,
+        })),
+        ("rect", [width, height]) => Some(EmitShape::Rect(RectData {
+            size: Vector2::new(*width, *height),
+            spawn_type,
+        })),
+        ("cone", [radius, direction, angle]) => Some(EmitShape::Cone(ConeData {
+            radius: *radius,
+            direction: direction.to_radians(),
+            angle: angle.to_radians(),
+            spawn_type,
+        })),
+        ("circle", [radius]) => Some(EmitShape::Circle(CircleData {
+            radius: *radius,
+            spawn_type,
+        })),
+        _ => None,
+    }
+}
+
+impl FromStr for ParticlePreset {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut preset = ParticlePreset::default();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let values = &words[1..];
+            let is_valid = match words[0] {
+                "amount" => values
+                    .first()
+                    .and_then(|v| v.parse().ok())
+                    .map(|v| preset.amount = v),
+                "sprite" => values.first().map(|v| preset.sprite = Some(v.to_string())),
+                "shape" => parse_shape(values).map(|v| preset.emit_shape = v),
+                "velocity" => parse_velocity(values).map(|v| preset.velocity_type = v),
+                "gravity" => values
+                    .first()
+                    .and_then(|v| v.parse().ok())
+                    .map(|v| preset.gravity = v),
+                "space" => match values {
+                    ["local"] => Some(TransformSpace::Local),
+                    ["world"] => Some(TransformSpace::World),
+                    _ => None,
+                }
+                .map(|v| preset.transform_space = v),
+                "lifetime" => parse_value(values).map(|v| preset.start_lifetime = v),
+                "speed" => parse_value(values).map(|v| preset.start_speed = v),
+                "rotation" => parse_value(values).map(|v| preset.start_rotation = v),
+                "scale" => parse_value(values).map(|v| preset.start_scale = v),
+                "angular_velocity" => {
+                    parse_value(values).map(|v| preset.start_angular_velocity = v)
+                }
+                "color" => parse_color(values).map(|v| preset.start_color = v),
+                "color_over_life" => parse_gradient(values).map(|v| preset.color_over_life = v),
+                "scale_over_life" => parse_curve(values).map(|v| preset.scale_over_life = v),
+                "speed_over_life" => parse_curve(values).map(|v| preset.speed_over_life = v),
+                _ => None,
+            };
+            if is_valid.is_none() {
+                return Err(format!("line {}: can't read '{}'", line_index + 1, line));
+            }
+        }
+        Ok(preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-5, "{} != {}", value, expected);
+    }
+
+    fn color(r: f32, g: f32, b: f32, a: f32) -> ggez::graphics::Color {
+        ggez::graphics::Color::new(r, g, b, a)
+    }
+
+    fn parse(text: &str) -> Result<ParticlePreset, String> {
+        text.parse::<ParticlePreset>()
+    }
+
+    #[test]
+    fn key_position_between_and_outside_keys() {
+        let keys = [(0.0, ()), (0.5, ()), (1.0, ())];
+        assert_eq!(key_position(&keys, 0.0), (0, 0.0));
+        assert_eq!(key_position(&keys, 0.25), (0, 0.5));
+        assert_eq!(key_position(&keys, 0.5), (1, 0.0));
+        assert_eq!(key_position(&keys, 0.75), (1, 0.5));
+        // Past the last key it stays on the last one
+        assert_eq!(key_position(&keys, 1.5), (2, 0.0));
+        // Before the first key it holds the first one
+        assert_eq!(key_position(&[(0.5, ())], 0.1), (0, 0.0));
+    }
+
+    #[test]
+    fn curve_sorts_its_keys() {
+        let curve = Curve::new(vec![(1.0, 0.0), (0.0, 1.0), (0.5, 4.0)]).unwrap();
+        assert_near(curve.get(0.0), 1.0);
+        assert_near(curve.get(0.25), 2.5);
+        assert_near(curve.get(0.75), 2.0);
+        assert_near(curve.get(1.0), 0.0);
+    }
+
+    #[test]
+    fn curve_holds_outside_its_keys() {
+        let curve = Curve::new(vec![(0.2, 3.0), (0.6, 1.0)]).unwrap();
+        assert_near(curve.get(0.0), 3.0);
+        assert_near(curve.get(0.4), 2.0);
+        assert_near(curve.get(2.0), 1.0);
+    }
+
+    #[test]
+    fn curve_with_one_key_is_constant() {
+        let curve = Curve::new(vec![(0.5, 2.0)]).unwrap();
+        for age in [0.0, 0.5, 1.0, 3.0].iter() {
+            assert_near(curve.get(*age), 2.0);
+        }
+        assert_near(Curve::constant(7.0).get(0.9), 7.0);
+        assert!(Curve::new(vec![]).is_none());
+    }
+
+    #[test]
+    fn gradient_blends_every_channel() {
+        let gradient = Gradient::new(vec![
+            (1.0, color(0.0, 1.0, 0.0, 0.0)),
+            (0.0, color(1.0, 0.0, 0.5, 1.0)),
+        ])
+        .unwrap();
+        let middle = gradient.get(0.5);
+        assert_near(middle.r, 0.5);
+        assert_near(middle.g, 0.5);
+        assert_near(middle.b, 0.25);
+        assert_near(middle.a, 0.5);
+        // Past the last key it holds the last color
+        assert_near(gradient.get(1.5).a, 0.0);
+        assert_near(
+            Gradient::constant(color(0.1, 0.2, 0.3, 0.4)).get(0.8).b,
+            0.3,
+        );
+        assert!(Gradient::new(vec![]).is_none());
+    }
+
+    #[test]
+    fn curve_needs_age_value_pairs() {
+        assert!(parse_curve(&["0", "1", "1"]).is_none());
+        assert!(parse_curve(&[]).is_none());
+        assert!(parse_curve(&["0", "x"]).is_none());
+        assert_near(parse_curve(&["1", "0", "0", "1"]).unwrap().get(0.0), 1.0);
+    }
+
+    #[test]
+    fn gradient_needs_groups_of_five() {
+        assert!(parse_gradient(&["0", "255", "255", "255"]).is_none());
+        assert!(parse_gradient(&["0", "255", "255", "255", "255", "1"]).is_none());
+        let gradient = parse_gradient(&["0", "255", "0", "0", "255"]).unwrap();
+        assert_near(gradient.get(0.5).r, 1.0);
+    }
+
+    #[test]
+    fn preset_settings() {
+        let preset = parse(
+            "# comment\n\
+             amount 12\n\
+             sprite straw\n\
+             shape circle 0.5 edge\n\
+             velocity angle 90 10\n\
+             space local\n\
+             lifetime 0.4 0.5\n\
+             color 255 0 0\n\
+             speed_over_life 0 1 1 0.5\n",
+        )
+        .unwrap();
+        assert_eq!(preset.amount, 12);
+        assert_eq!(preset.sprite.as_deref(), Some("straw"));
+        assert!(matches!(preset.emit_shape, EmitShape::Circle(_)));
+        assert!(matches!(preset.velocity_type, VelocityType::Angle(_)));
+        assert!(matches!(preset.transform_space, TransformSpace::Local));
+        assert!(matches!(preset.start_lifetime, ValueGetter::Range(_, _)));
+        assert!(matches!(preset.start_color, ValueGetter::Single(_)));
+        assert_near(preset.speed_over_life.get(1.0), 0.5);
+        // Settings not in the file keep the default
+        assert_near(preset.gravity, -9.0);
+    }
+
+    #[test]
+    fn preset_errors_name_the_line() {
+        let error = |text| parse(text).err().unwrap();
+        assert_eq!(
+            error("amount 3\nscale_over_life 0 1 1\n"),
+            "line 2: can't read 'scale_over_life 0 1 1'"
+        );
+        assert_eq!(error("speed 1 2 3"), "line 1: can't read 'speed 1 2 3'");
+        assert_eq!(
+            error("shape cone 1 2 volume"),
+            "line 1: can't read 'shape cone 1 2 volume'"
+        );
+        assert_eq!(error("\n\nwobble 1"), "line 3: can't read 'wobble 1'");
+    }
+
+    #[test]
+    fn every_shipped_preset_is_valid() {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/particles");
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            let text = std::fs::read_to_string(&path).unwrap();
+            if let Err(e) = parse(&text) {
+                panic!("{}: {}", path.display(), e);
+            }
+        }
+    }
 }
@@ -0,0 +1,348 @@
+use crate::campaign::Campaign;
+use crate::storage;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+const PROGRESS_KEY: &str = "progress";
+const PROGRESS_HEADER: &str = "dig_escape_progress 2";
+// Version 1 had the map name first, which broke on names with spaces. It's still read
+const OLD_PROGRESS_HEADER: &str = "dig_escape_progress 1";
+
+#[derive(Default, Clone)]
+pub struct LevelProgress {
+    pub completed: bool,
+    pub best_moves: Option<u32>,
+    pub stars: u8,          // best rating, 0 if the level has no par
+    pub hints: Option<u32>, // fewest hints used to complete the level
+}
+
+// 3 stars at or under par, 2 stars within one and a half par, 1 star for escaping at all
+pub fn star_rating(moves: u32, par: u32) -> u8 {
+    if moves <= par {
+        3
+    } else if moves * 2 <= par * 3 {
+        2
+    } else {
+        1
+    }
+}
+
+// What the player has done so far, saved between sessions
+// Levels are keyed by map name, so reordering the campaign keeps the results
+//
+// dig_escape_progress 2
+// level 6 3 0 /maps/map_first.txt
+// level 9 2 1 /maps/map_1skeleton.txt
+//
+// level best_moves stars hints map_name, the map name is the rest of the line so it can have spaces.
+// A level completed before moves were counted has - for the best moves and hints
+#[derive(Default)]
+pub struct Progress {
+    pub levels: HashMap<String, LevelProgress>,
+}
+
+impl Progress {
+    // Missing or broken saves start from scratch, they should never stop the game.
+    // A broken save is kept as progress.bak first, so the next save doesn't lose it
+    pub fn load() -> Progress {
+        match storage::load(PROGRESS_KEY) {
+            Some(text) => text.parse::<Progress>().unwrap_or_else(|e| {
+                eprintln!("could not read saved progress: {}", e);
+                let backup_key = format!("{}.bak", PROGRESS_KEY);
+                match storage::save(&backup_key, &text) {
+                    Ok(()) => eprintln!("kept the unreadable progress as {}", backup_key),
+                    Err(e) => eprintln!("could not keep the unreadable progress: {}", e),
+                }
+                Progress::default()
+            }),
+            None => Progress::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(PROGRESS_KEY, &self.to_string()) {
+            eprintln!("could not save progress: {}", e);
+        }
+    }
+
+    pub fn level(&self, map_name: &str) -> Option<&LevelProgress> {
+        self.levels.get(map_name)
+    }
+
+    pub fn is_completed(&self, map_name: &str) -> bool {
+        self.level(map_name).map_or(false, |level| level.completed)
+    }
+
+    // Index of the furthest level reached, the one after the last completed level of the campaign.
+    // Worked out from the completed map names, so it follows the levels when the campaign is reordered
+    pub fn highest_level(&self, campaign: &Campaign) -> usize {
+        (0..campaign.len())
+            .rev()
+            .find(|index| {
+                campaign
+                    .map_name(*index)
+                    .map_or(false, |name| self.is_completed(name))
+            })
+            .map_or(0, |index| index + 1)
+    }
+
+    // Returns true if this is a new best
+    pub fn complete_level(&mut self, map_name: &str, moves: u32, stars: u8, hints: u32) -> bool {
+        let level = self.levels.entry(map_name.to_string()).or_default();
+        level.completed = true;
+        level.stars = level.stars.max(stars);
+        level.hints = Some(level.hints.map_or(hints, |best| best.min(hints)));
+        match level.best_moves {
+            Some(best) if best <= moves => false,
+            _ => {
+                level.best_moves = Some(moves);
+                true
+            }
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", PROGRESS_HEADER)?;
+        let mut map_names: Vec<&String> = self.levels.keys().collect();
+        map_names.sort();
+        for map_name in map_names {
+            let level = &self.levels[map_name];
+            if !level.completed {
+                continue;
+            }
+            let or_dash = |value: Option<u32>| value.map_or("-".to_string(), |v| v.to_string());
+            writeln!(
+                f,
+                "level {} {} {} {}",
+                or_dash(level.best_moves),
+                level.stars,
+                or_dash(level.hints),
+                map_name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: FromStr>(word: &str) -> Option<Option<T>> {
+    match word {
+        "-" => Some(None),
+        _ => word.parse().ok().map(Some),
+    }
+}
+
+// level best_moves stars hints map name
+// None if the line can't be read, Some(None) for a line without a level to keep
+fn parse_line(line: &str) -> Option<Option<(String, LevelProgress)>> {
+    let mut words = line.splitn(5, ' ');
+    if words.next() != Some("level") {
+        return None;
+    }
+    let best_moves = parse_number(words.next()?)?;
+    let stars = words.next()?.parse().ok()?;
+    let hints = parse_number(words.next()?)?;
+    let map_name = words
+        .next()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())?;
+    let level = LevelProgress {
+        completed: true,
+        best_moves,
+        stars,
+        hints,
+    };
+    Some(Some((map_name.to_string(), level)))
+}
+
+// Version 1: level map_name [best_moves [stars [hints]]], read like parse_line
+fn parse_old_line(line: &str) -> Option<Option<(String, LevelProgress)>> {
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        // The furthest level was a campaign index, it comes from the levels now
+        "highest_level" => Some(None),
+        "level" => {
+            let map_name = words.next()?;
+            let best_moves = match words.next() {
+                Some(moves) => Some(moves.parse().ok()?),
+                None => None,
+            };
+            let stars = match words.next() {
+                Some(stars) => stars.parse().ok()?,
+                None => 0,
+            };
+            let hints = match words.next() {
+                Some(hints) => Some(hints.parse().ok()?),
+                None => None,
+            };
+            let level = LevelProgress {
+                completed: true,
+                best_moves,
+                stars,
+                hints,
+            };
+            Some(Some((map_name.to_string(), level)))
+        }
+        _ => None,
+    }
+}
+
+impl FromStr for Progress {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().enumerate();
+        let is_old = match lines.next() {
+            Some((_, header)) if header.trim() == PROGRESS_HEADER => false,
+            Some((_, header)) if header.trim() == OLD_PROGRESS_HEADER => true,
+            _ => return Err(format!("expected '{}'", PROGRESS_HEADER)),
+        };
+
+        let mut progress = Progress::default();
+        for (line_index, line) in lines {
+            let invalid_line = || format!("line {}: can't read '{}'", line_index + 1, line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parsed = match is_old {
+                true => parse_old_line(line),
+                false => parse_line(line),
+            };
+            if let Some((map_name, level)) = parsed.ok_or_else(invalid_line)? {
+                progress.levels.insert(map_name, level);
+            }
+        }
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign;
+
+    fn parse(text: &str) -> Result<Progress, String> {
+        text.parse::<Progress>()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut progress = Progress::default();
+        progress.complete_level("/maps/map_first.txt", 6, 3, 0);
+        progress.complete_level("/maps/my level.txt", 9, 2, 1);
+        let text = progress.to_string();
+        assert_eq!(
+            text,
+            "dig_escape_progress 2\n\
+             level 6 3 0 /maps/map_first.txt\n\
+             level 9 2 1 /maps/my level.txt\n"
+        );
+        let read = parse(&text).unwrap();
+        let level = read.level("/maps/my level.txt").unwrap();
+        assert!(level.completed);
+        assert_eq!(
+            (level.best_moves, level.stars, level.hints),
+            (Some(9), 2, Some(1))
+        );
+        assert!(read.is_completed("/maps/map_first.txt"));
+    }
+
+    #[test]
+    fn level_without_moves() {
+        let mut progress = Progress::default();
+        progress.levels.insert(
+            "/maps/a.txt".to_string(),
+            LevelProgress {
+                completed: true,
+                ..LevelProgress::default()
+            },
+        );
+        let text = progress.to_string();
+        assert_eq!(text, "dig_escape_progress 2\nlevel - 0 - /maps/a.txt\n");
+        let level = parse(&text).unwrap().levels["/maps/a.txt"].clone();
+        assert_eq!((level.best_moves, level.hints), (None, None));
+    }
+
+    #[test]
+    fn reads_version_1() {
+        let progress = parse(
+            "dig_escape_progress 1\n\
+             highest_level 3\n\
+             level /maps/map_first.txt 6 3 0\n\
+             level /maps/map_1skeleton.txt\n",
+        )
+        .unwrap();
+        assert_eq!(progress.levels.len(), 2);
+        assert_eq!(progress.levels["/maps/map_first.txt"].best_moves, Some(6));
+        assert_eq!(progress.levels["/maps/map_1skeleton.txt"].best_moves, None);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("level 6 3 0 /maps/a.txt\n").err().unwrap(),
+            "expected 'dig_escape_progress 2'"
+        );
+        for (text, expected) in [
+            ("level 6 3 0", "line 2: can't read 'level 6 3 0'"),
+            (
+                "level six 3 0 /maps/a.txt",
+                "line 2: can't read 'level six 3 0 /maps/a.txt'",
+            ),
+            (
+                "level 6 300 0 /maps/a.txt",
+                "line 2: can't read 'level 6 300 0 /maps/a.txt'",
+            ),
+            ("highest_level 3", "line 2: can't read 'highest_level 3'"),
+        ]
+        .iter()
+        {
+            let text = format!("dig_escape_progress 2\n{}\n", text);
+            assert_eq!(parse(&text).err().unwrap(), *expected);
+        }
+        assert_eq!(
+            parse("dig_escape_progress 1\nlevel /maps/a.txt x\n")
+                .err()
+                .unwrap(),
+            "line 2: can't read 'level /maps/a.txt x'"
+        );
+    }
+
+    #[test]
+    fn best_results_are_kept() {
+        let mut progress = Progress::default();
+        assert!(progress.complete_level("/maps/a.txt", 10, 2, 1));
+        assert!(!progress.complete_level("/maps/a.txt", 12, 3, 0));
+        assert!(progress.complete_level("/maps/a.txt", 8, 1, 2));
+        let level = progress.level("/maps/a.txt").unwrap();
+        assert_eq!(
+            (level.best_moves, level.stars, level.hints),
+            (Some(8), 3, Some(0))
+        );
+    }
+
+    #[test]
+    fn stars() {
+        assert_eq!(star_rating(5, 10), 3);
+        assert_eq!(star_rating(10, 10), 3);
+        assert_eq!(star_rating(11, 10), 2);
+        assert_eq!(star_rating(15, 10), 2);
+        assert_eq!(star_rating(16, 10), 1);
+        assert_eq!(star_rating(1, 0), 1);
+    }
+
+    #[test]
+    fn highest_level_follows_the_map_names() {
+        let campaign = campaign::parse_campaign("a.txt | A\nb.txt | B\nc.txt | C\n").unwrap();
+        let mut progress = Progress::default();
+        assert_eq!(progress.highest_level(&campaign), 0);
+        progress.complete_level(campaign.map_name(1).unwrap(), 5, 3, 0);
+        assert_eq!(progress.highest_level(&campaign), 2);
+        // Moving the completed level to the end of the campaign moves the furthest level with it
+        let reordered = campaign::parse_campaign("b.txt | B\na.txt | A\nc.txt | C\n").unwrap();
+        assert_eq!(progress.highest_level(&reordered), 1);
+    }
+}
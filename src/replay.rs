@@ -0,0 +1,252 @@
+use crate::entities::player::PlayerInputIntent;
+use std::fmt;
+use std::str::FromStr;
+
+const REPLAY_HEADER: &str = "dig_escape_replay 1";
+
+// One attempt at a level, enough to play it again exactly:
+// which map, the seed used for decoration, and every move with the turn it was made on
+//
+// dig_escape_replay 1
+// map /maps/map_middleclash_2.txt
+// seed 1234
+// inputs 0R 1R 2D 5U
+pub struct Replay {
+    pub map_name: String,
+    pub seed: u64,
+    pub inputs: Vec<(u32, PlayerInputIntent)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    MissingHeader,
+    MissingField(&'static str),
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::MissingHeader => {
+                write!(f, "not a replay, expected '{}'", REPLAY_HEADER)
+            }
+            ReplayError::MissingField(field) => write!(f, "replay is missing '{}'", field),
+            ReplayError::InvalidLine(line, text) => {
+                write!(f, "line {}: can't read '{}'", line, text)
+            }
+        }
+    }
+}
+
+fn intent_to_char(intent: PlayerInputIntent) -> char {
+    match intent {
+        PlayerInputIntent::Up => 'U',
+        PlayerInputIntent::Left => 'L',
+        PlayerInputIntent::Right => 'R',
+        PlayerInputIntent::Down => 'D',
+        PlayerInputIntent::None => 'N',
+    }
+}
+
+fn intent_from_char(c: char) -> Option<PlayerInputIntent> {
+    match c {
+        'U' => Some(PlayerInputIntent::Up),
+        'L' => Some(PlayerInputIntent::Left),
+        'R' => Some(PlayerInputIntent::Right),
+        'D' => Some(PlayerInputIntent::Down),
+        'N' => Some(PlayerInputIntent::None),
+        _ => None,
+    }
+}
+
+impl Replay {
+    pub fn new(map_name: &str, seed: u64) -> Self {
+        Replay {
+            map_name: map_name.to_string(),
+            seed,
+            inputs: vec![],
+        }
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        text.parse::<Replay>().map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", REPLAY_HEADER)?;
+        writeln!(f, "map {}", self.map_name)?;
+        writeln!(f, "seed {}", self.seed)?;
+        write!(f, "inputs")?;
+        for (turn, intent) in self.inputs.iter() {
+            write!(f, " {}{}", turn, intent_to_char(*intent))?;
+        }
+        writeln!(f)
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == REPLAY_HEADER => {}
+            _ => return Err(ReplayError::MissingHeader),
+        }
+
+        let mut map_name = None;
+        let mut seed = None;
+        let mut inputs = vec![];
+        for (line_index, line) in lines {
+            let line_number = line_index + 1;
+            let invalid_line = || ReplayError::InvalidLine(line_number, line.to_string());
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("map") => {
+                    let value = words.next().ok_or_else(invalid_line)?;
+                    map_name = Some(value.to_string());
+                }
+                Some("seed") => {
+                    let value = words.next().ok_or_else(invalid_line)?;
+                    seed = Some(value.parse::<u64>().map_err(|_| invalid_line())?);
+                }
+                Some("inputs") => {
+                    for word in words {
+                        // turn number followed by a single intent letter, e.g. 12R
+                        let intent_char = word.chars().last().ok_or_else(invalid_line)?;
+                        let intent = intent_from_char(intent_char).ok_or_else(invalid_line)?;
+                        let turn = word[..word.len() - 1]
+                            .parse::<u32>()
+                            .map_err(|_| invalid_line())?;
+                        inputs.push((turn, intent));
+                    }
+                }
+                Some(_) => return Err(invalid_line()),
+                None => {}
+            }
+        }
+
+        Ok(Replay {
+            map_name: map_name.ok_or(ReplayError::MissingField("map"))?,
+            seed: seed.ok_or(ReplayError::MissingField("seed"))?,
+            inputs,
+        })
+    }
+}
+
+// Feeds the inputs of a replay back, one by one, when their turn comes
+pub struct Playback {
+    pub replay: Replay,
+    next_input: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            replay,
+            next_input: 0,
+        }
+    }
+
+    pub fn next_intent(&mut self, turn: u32) -> Option<PlayerInputIntent> {
+        match self.replay.inputs.get(self.next_input) {
+            Some((input_turn, intent)) if *input_turn <= turn => {
+                self.next_input += 1;
+                Some(*intent)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_input >= self.replay.inputs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> ReplayError {
+        match text.parse::<Replay>() {
+            Ok(_) => panic!("expected an error for {:?}", text),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut replay = Replay::new("/maps/map_first.txt", 1234);
+        replay.inputs = vec![
+            (0, PlayerInputIntent::Right),
+            (1, PlayerInputIntent::Down),
+            (12, PlayerInputIntent::Up),
+            (13, PlayerInputIntent::Left),
+        ];
+        let text = replay.to_string();
+        assert_eq!(
+            text,
+            "dig_escape_replay 1\nmap /maps/map_first.txt\nseed 1234\ninputs 0R 1D 12U 13L\n"
+        );
+        let read = text.parse::<Replay>().unwrap();
+        assert_eq!(read.map_name, replay.map_name);
+        assert_eq!(read.seed, replay.seed);
+        assert_eq!(read.inputs, replay.inputs);
+    }
+
+    #[test]
+    fn no_inputs() {
+        let replay = "dig_escape_replay 1\nseed 7\nmap /maps/a.txt\n\ninputs\n"
+            .parse::<Replay>()
+            .unwrap();
+        assert_eq!(replay.seed, 7);
+        assert!(replay.inputs.is_empty());
+    }
+
+    #[test]
+    fn missing_header_or_field() {
+        assert!(matches!(
+            error("map /maps/a.txt\nseed 1\n"),
+            ReplayError::MissingHeader
+        ));
+        assert!(matches!(
+            error("dig_escape_replay 1\nseed 1\n"),
+            ReplayError::MissingField("map")
+        ));
+        assert!(matches!(
+            error("dig_escape_replay 1\nmap /maps/a.txt\n"),
+            ReplayError::MissingField("seed")
+        ));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let header = "dig_escape_replay 1\nmap /maps/a.txt\nseed 1\n";
+        for (line, expected_line) in [
+            ("inputs 0R 3X", 4),
+            ("inputs R", 4),
+            ("inputs -1R", 4),
+            ("seed -5", 4),
+            ("speed 3", 4),
+        ]
+        .iter()
+        {
+            match error(&format!("{}{}\n", header, line)) {
+                ReplayError::InvalidLine(number, text) => {
+                    assert_eq!((number, text.as_str()), (*expected_line, *line))
+                }
+                e => panic!("{}: {}", line, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::entities::player::{self, PlayerInputIntent};
+use crate::entities::skeleton;
+use crate::events::{EventBus, GameEvent};
+use crate::states::game_state::GameState;
+use nalgebra as na;
+
+// Grass, skeletons and skeleton blocks are solid, anything standing on them is grounded
+pub fn is_solid(game_state: &GameState, position: &na::Point2<i32>) -> bool {
+    game_state.grid.is_solid(position)
+}
+
+pub fn is_player_grounded(game_state: &GameState) -> bool {
+    let pos_below = game_state.player.transform.position + na::Vector2::new(0, 1);
+    is_solid(game_state, &pos_below)
+}
+
+// Resolves one full turn: the player acts (or falls), skeletons act, buried skeletons get released
+// Doesn't need a Context, so it can run without a window, sounds or particles.
+// Returns what happened during the turn
+pub fn simulate_turn(game_state: &mut GameState, intent: PlayerInputIntent) -> EventBus {
+    let mut events = EventBus::default();
+    if !game_state.player.is_alive {
+        return events;
+    }
+    game_state.turn += 1;
+
+    let pos_below = game_state.player.transform.position + na::Vector2::new(0, 1);
+    game_state.player.is_on_skeleton = game_state.grid.has_skeleton(&pos_below);
+
+    player::system(game_state, intent, &mut events);
+    if events.contains(&GameEvent::LevelCompleted) {
+        return events;
+    }
+    skeleton::system(game_state, &mut events);
+    skeleton::block_system(game_state, &mut events);
+    events
+}
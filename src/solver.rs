@@ -0,0 +1,335 @@
+use crate::entities::ai::AiState;
+use crate::entities::player::PlayerInputIntent;
+use crate::events::GameEvent;
+use crate::simulation;
+use crate::states::game_state::GameState;
+use std::collections::{HashSet, VecDeque};
+
+pub const DEFAULT_MAX_STATES: usize = 500_000;
+// Hints are searched inside a key press, this keeps the frame short and still
+// solves every campaign level from its start
+pub const HINT_MAX_STATES: usize = 20_000;
+
+// A fall longer than this wraps around forever, the player never lands again
+fn max_fall_steps(game_state: &GameState) -> i32 {
+    game_state.map_size.y * 4
+}
+
+const INTENTS: [PlayerInputIntent; 4] = [
+    PlayerInputIntent::Left,
+    PlayerInputIntent::Right,
+    PlayerInputIntent::Up,
+    PlayerInputIntent::Down,
+];
+
+pub enum SolveResult {
+    Solved(Vec<PlayerInputIntent>), // shortest winning move sequence
+    Unsolvable,                     // every reachable state was searched
+    GaveUp,                         // ran into the max state limit before finding anything
+}
+
+// Only what the rules look at, foilage and sprites don't decide anything
+#[derive(PartialEq, Eq, Hash)]
+struct StateKey {
+    player: (i32, i32),
+    skeletons: Vec<(i32, i32, bool)>,
+    skeleton_blocks: Vec<(bool, bool)>,
+}
+
+impl StateKey {
+    fn new(game_state: &GameState) -> Self {
+        let position = game_state.player.transform.position;
+        StateKey {
+            player: (position.x, position.y),
+            skeletons: game_state
+                .skeletons
+                .iter()
+                .map(|s| {
+                    let p = s.transform.position;
+                    (p.x, p.y, s.ai.state == AiState::Attack)
+                })
+                .collect(),
+            skeleton_blocks: game_state
+                .skeleton_blocks
+                .iter()
+                .map(|b| (b.buried.is_dug, b.buried.is_released))
+                .collect(),
+        }
+    }
+}
+
+// Result of one player move, falling included
+pub enum MoveResult {
+    Won,
+    Lost,
+    Continue(GameState),
+}
+
+// Plays one move the way the game does: the intent turn,
+// then automatic steps until the player stands on something again
+pub fn resolve_move(game_state: &GameState, intent: PlayerInputIntent) -> MoveResult {
+    let mut state = game_state.clone();
+    let events = simulation::simulate_turn(&mut state, intent);
+    if events.contains(&GameEvent::LevelCompleted) {
+        return MoveResult::Won;
+    }
+
+    let mut fall_steps = 0;
+    while state.player.is_alive && !simulation::is_player_grounded(&state) {
+        if fall_steps > max_fall_steps(&state) {
+            return MoveResult::Lost;
+        }
+        simulation::simulate_turn(&mut state, PlayerInputIntent::None);
+        fall_steps += 1;
+    }
+
+    if !state.player.is_alive {
+        return MoveResult::Lost;
+    }
+    MoveResult::Continue(state)
+}
+
+// Breadth first search over the player moves, so the first solution found is the shortest
+pub fn solve(game_state: &GameState, max_states: usize) -> SolveResult {
+    let mut start = game_state.clone();
+    // Decoration only slows down cloning
+    start.clouds.clear();
+    start.foilages.clear();
+
+    if !start.player.is_alive {
+        return SolveResult::Unsolvable;
+    }
+
+    // (parent node, intent that led here)
+    let mut nodes: Vec<(usize, PlayerInputIntent)> = vec![(0, PlayerInputIntent::None)];
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    // Starting mid air, fall first like the game would
+    if !simulation::is_player_grounded(&start) {
+        start = match resolve_move(&start, PlayerInputIntent::None) {
+            MoveResult::Continue(state) => state,
+            _ => return SolveResult::Unsolvable,
+        };
+    }
+    visited.insert(StateKey::new(&start));
+    queue.push_back((0, start));
+
+    while let Some((node_index, state)) = queue.pop_front() {
+        for intent in INTENTS.iter() {
+            match resolve_move(&state, *intent) {
+                MoveResult::Won => {
+                    let mut moves = vec![*intent];
+                    let mut index = node_index;
+                    while index != 0 {
+                        let (parent, parent_intent) = nodes[index];
+                        moves.push(parent_intent);
+                        index = parent;
+                    }
+                    moves.reverse();
+                    return SolveResult::Solved(moves);
+                }
+                MoveResult::Lost => {}
+                MoveResult::Continue(next_state) => {
+                    if !visited.insert(StateKey::new(&next_state)) {
+                        continue;
+                    }
+                    if visited.len() > max_states {
+                        return SolveResult::GaveUp;
+                    }
+                    nodes.push((node_index, *intent));
+                    queue.push_back((nodes.len() - 1, next_state));
+                }
+            }
+        }
+    }
+    SolveResult::Unsolvable
+}
+
+// Moves the game counts for a solution, every turn the player acts on except leaving through the exit
+pub fn count_moves(game_state: &GameState, moves: &[PlayerInputIntent]) -> u32 {
+    play_moves(game_state, moves).move_count
+}
+
+// The state after playing the moves, stops early if the level is won or lost
+pub fn play_moves(game_state: &GameState, moves: &[PlayerInputIntent]) -> GameState {
+    let mut state = game_state.clone();
+    for intent in moves.iter() {
+        match resolve_move(&state, *intent) {
+            MoveResult::Continue(next_state) => state = next_state,
+            MoveResult::Won => {
+                simulation::simulate_turn(&mut state, *intent);
+                break;
+            }
+            MoveResult::Lost => break,
+        }
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign;
+    use crate::map;
+
+    fn load(text: &str) -> GameState {
+        let mut game_state = GameState::new();
+        map::build_map(&mut game_state, &map::parse_map(text).unwrap());
+        game_state
+    }
+
+    fn solution(game_state: &GameState) -> Vec<PlayerInputIntent> {
+        match solve(game_state, DEFAULT_MAX_STATES) {
+            SolveResult::Solved(moves) => moves,
+            SolveResult::Unsolvable => panic!("unsolvable"),
+            SolveResult::GaveUp => panic!("gave up"),
+        }
+    }
+
+    // Plays the moves with simulate_turn the way the game does, without the solver's helpers:
+    // one turn per move, then turns without input while the player falls
+    fn wins(game_state: &GameState, moves: &[PlayerInputIntent]) -> bool {
+        let mut state = game_state.clone();
+        for intent in moves.iter() {
+            if simulation::simulate_turn(&mut state, *intent).contains(&GameEvent::LevelCompleted) {
+                return true;
+            }
+            let mut fall_steps = 0;
+            while state.player.is_alive && !simulation::is_player_grounded(&state) {
+                if fall_steps > max_fall_steps(&state) {
+                    return false;
+                }
+                simulation::simulate_turn(&mut state, PlayerInputIntent::None);
+                fall_steps += 1;
+            }
+        }
+        false
+    }
+
+    // Every move sequence of the given length, to check nothing shorter wins
+    fn all_sequences(length: usize) -> Vec<Vec<PlayerInputIntent>> {
+        let mut sequences = vec![vec![]];
+        for _ in 0..length {
+            sequences = sequences
+                .iter()
+                .flat_map(|sequence| {
+                    INTENTS.iter().map(move |intent| {
+                        let mut longer = sequence.clone();
+                        longer.push(*intent);
+                        longer
+                    })
+                })
+                .collect();
+        }
+        sequences
+    }
+
+    fn assert_shortest(text: &str, expected_length: usize) {
+        let game_state = load(text);
+        let moves = solution(&game_state);
+        assert_eq!(moves.len(), expected_length, "{:?}", moves);
+        assert!(wins(&game_state, &moves), "{:?} doesn't win", moves);
+        for length in 1..expected_length {
+            for sequence in all_sequences(length) {
+                assert!(!wins(&game_state, &sequence), "{:?} is shorter", sequence);
+            }
+        }
+    }
+
+    #[test]
+    fn walk_to_the_exit() {
+        use PlayerInputIntent::*;
+        let game_state = load("0-4\n111\n");
+        assert_eq!(solution(&game_state), vec![Right, Right, Up]);
+        assert_shortest("0-4\n111\n", 3);
+        // The exit doesn't count as a move
+        assert_eq!(count_moves(&game_state, &[Right, Right, Up]), 2);
+    }
+
+    #[test]
+    fn fall_down_to_the_exit() {
+        assert_shortest("0\n1-4\n111\n", 3);
+    }
+
+    #[test]
+    fn teleport_to_the_exit() {
+        use PlayerInputIntent::*;
+        let text = "03-1--3-4\n111---111\n";
+        assert_eq!(solution(&load(text)), vec![Right, Up, Right, Right, Up]);
+        assert_shortest(text, 5);
+    }
+
+    #[test]
+    fn exit_waits_for_the_skeleton() {
+        use PlayerInputIntent::*;
+        // Walking straight to the exit isn't enough, the skeleton under the player comes first
+        let text = "-0--4\n12111\n11111\n";
+        assert_eq!(solution(&load(text)), vec![Down, Right, Right, Right, Up]);
+        assert_shortest(text, 5);
+        assert_eq!(
+            count_moves(&load(text), &[Down, Right, Right, Right, Up]),
+            4
+        );
+    }
+
+    #[test]
+    fn every_turn_counts() {
+        use PlayerInputIntent::*;
+        // Into the wall, up off a teleporter, down on plain ground, then out through the exit
+        let game_state = load("0-4\n111\n");
+        assert_eq!(
+            count_moves(&game_state, &[Left, Up, Down, Right, Right, Up]),
+            5
+        );
+        let state = play_moves(&game_state, &[Left]);
+        assert_eq!(state.turn, game_state.turn + 1);
+    }
+
+    #[test]
+    fn walled_in_is_unsolvable() {
+        let game_state = load("0-1\n111\n---4\n---1\n");
+        assert!(matches!(
+            solve(&game_state, DEFAULT_MAX_STATES),
+            SolveResult::Unsolvable
+        ));
+    }
+
+    #[test]
+    fn gives_up_at_the_state_limit() {
+        let game_state = load("0-------4\n111111111\n");
+        assert!(matches!(solve(&game_state, 2), SolveResult::GaveUp));
+    }
+
+    #[test]
+    fn every_campaign_map_is_solvable_within_par() {
+        let resources = concat!(env!("CARGO_MANIFEST_DIR"), "/resources");
+        let path = format!("{}{}", resources, campaign::CAMPAIGN_FILE);
+        let campaign = campaign::parse_campaign(&std::fs::read_to_string(path).unwrap()).unwrap();
+        for level in campaign.levels.iter() {
+            let text = std::fs::read_to_string(format!("{}{}", resources, level.map_name));
+            let game_state = load(&text.unwrap());
+            // The hint budget is enough for every level, so a hint is always found from the start
+            match solve(&game_state, HINT_MAX_STATES) {
+                SolveResult::Solved(moves) => {
+                    assert!(
+                        wins(&game_state, &moves),
+                        "{} solution doesn't win",
+                        level.map_name
+                    );
+                    let counted_moves = count_moves(&game_state, &moves);
+                    if let Some(par) = level.par {
+                        assert!(
+                            par >= counted_moves,
+                            "{} par {} is below {}",
+                            level.map_name,
+                            par,
+                            counted_moves
+                        );
+                    }
+                }
+                _ => panic!("{} isn't solvable", level.map_name),
+            }
+        }
+    }
+}
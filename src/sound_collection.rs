@@ -1,20 +1,359 @@
 use gwg as ggez;
 
+use crate::events::GameEvent;
+use crate::storage;
 use ggez::{audio, GameResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+const SETTINGS_KEY: &str = "audio";
+const SETTINGS_HEADER: &str = "dig_escape_audio 1";
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    PlayerWalk,
+    PlayerDig,
+    PlayerHit,
+    PlayerTeleport,
+    PlayerFall,
+    PlayerLand,
+    LevelCompleted,
+    LevelRestarted,
+    SkeletonAttack,
+    DoorLocked,
+}
+
+impl Sound {
+    // Every sound the game plays, a new sound needs a variant, an entry here
+    // and a line in resources/assets.txt
+    pub const ALL: &'static [Sound] = &[
+        Sound::PlayerWalk,
+        Sound::PlayerDig,
+        Sound::PlayerHit,
+        Sound::PlayerTeleport,
+        Sound::PlayerFall,
+        Sound::PlayerLand,
+        Sound::LevelCompleted,
+        Sound::LevelRestarted,
+        Sound::SkeletonAttack,
+        Sound::DoorLocked,
+    ];
+
+    // Role of the sound in the asset manifest
+    pub fn name(self) -> &'static str {
+        match self {
+            Sound::PlayerWalk => "player_walk",
+            Sound::PlayerDig => "player_dig",
+            Sound::PlayerHit => "player_hit",
+            Sound::PlayerTeleport => "player_teleport",
+            Sound::PlayerFall => "player_fall",
+            Sound::PlayerLand => "player_land",
+            Sound::LevelCompleted => "level_completed",
+            Sound::LevelRestarted => "level_restarted",
+            Sound::SkeletonAttack => "skeleton_attack",
+            Sound::DoorLocked => "door_locked",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Sound> {
+        Sound::ALL.iter().copied().find(|sound| sound.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Master,
+    Sfx,
+    Music,
+}
+
+// Volumes go from 0 to 1, the sound effects and the music are both scaled by the master volume
+//
+// dig_escape_audio 1
+// muted 0
+// master 0.8
+// sfx 1
+// music 0.5
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioSettings {
+    pub is_muted: bool,
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            is_muted: false,
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.5,
+        }
+    }
+}
+
+impl AudioSettings {
+    // Missing or broken settings fall back to the defaults
+    pub fn load() -> AudioSettings {
+        match storage::load(SETTINGS_KEY) {
+            Some(text) => text.parse::<AudioSettings>().unwrap_or_else(|e| {
+                eprintln!("could not read audio settings: {}", e);
+                AudioSettings::default()
+            }),
+            None => AudioSettings::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save(SETTINGS_KEY, &self.to_string()) {
+            eprintln!("could not save audio settings: {}", e);
+        }
+    }
+
+    pub fn volume(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master_volume,
+            Channel::Sfx => self.sfx_volume,
+            Channel::Music => self.music_volume,
+        }
+    }
+
+    fn volume_mut(&mut self, channel: Channel) -> &mut f32 {
+        match channel {
+            Channel::Master => &mut self.master_volume,
+            Channel::Sfx => &mut self.sfx_volume,
+            Channel::Music => &mut self.music_volume,
+        }
+    }
+
+    // What a source on the channel actually plays at
+    pub fn output_volume(&self, channel: Channel) -> f32 {
+        match self.is_muted {
+            true => 0.0,
+            false => self.master_volume * self.volume(channel),
+        }
+    }
+}
+
+impl fmt::Display for AudioSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", SETTINGS_HEADER)?;
+        writeln!(f, "muted {}", self.is_muted as u8)?;
+        writeln!(f, "master {}", self.master_volume)?;
+        writeln!(f, "sfx {}", self.sfx_volume)?;
+        writeln!(f, "music {}", self.music_volume)
+    }
+}
+
+impl FromStr for AudioSettings {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == SETTINGS_HEADER => {}
+            _ => return Err(format!("expected '{}'", SETTINGS_HEADER)),
+        }
+
+        let mut settings = AudioSettings::default();
+        for (line_index, line) in lines {
+            let invalid_line = || format!("line {}: can't read '{}'", line_index + 1, line);
+            let mut words = line.split_whitespace();
+            let channel = match words.next() {
+                Some("muted") => {
+                    let value: u8 = words
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(invalid_line)?;
+                    settings.is_muted = value != 0;
+                    continue;
+                }
+                Some("master") => Channel::Master,
+                Some("sfx") => Channel::Sfx,
+                Some("music") => Channel::Music,
+                Some(_) => return Err(invalid_line()),
+                None => continue,
+            };
+            let value: f32 = words
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(invalid_line)?;
+            *settings.volume_mut(channel) = value.max(0.0).min(1.0);
+        }
+        Ok(settings)
+    }
+}
 
 pub struct SoundCollection {
-    pub sounds: [audio::Source; 10],
-    pub is_on: bool,
+    pub sounds: HashMap<Sound, audio::Source>,
+    pub music: Option<audio::Source>, // loops from startup
+    pub settings: AudioSettings,
 }
 
 impl SoundCollection {
-    pub fn play(&mut self, index: usize) -> GameResult<()> {
-        if !self.is_on {
+    // Sounds come from the asset manifest, the game plays without music if there is none
+    pub fn new(sounds: HashMap<Sound, audio::Source>, music: Option<audio::Source>) -> Self {
+        let music = music.map(|mut music| {
+            music.set_repeat(true);
+            music
+        });
+        let mut sound_collection = SoundCollection {
+            sounds,
+            music,
+            settings: AudioSettings::load(),
+        };
+        sound_collection.apply_volumes();
+        sound_collection
+    }
+
+    pub fn is_on(&self) -> bool {
+        !self.settings.is_muted
+    }
+
+    pub fn play(&mut self, sound: Sound) -> GameResult<()> {
+        if self.settings.is_muted {
             return Ok(());
         }
-        if let Some(source) = self.sounds.get_mut(index) {
-            source.play()?;
+        match self.sounds.get_mut(&sound) {
+            Some(source) => source.play(),
+            None => Err(ggez::error::GameError::SoundError),
+        }
+    }
+
+    pub fn start_music(&mut self) -> GameResult<()> {
+        match &mut self.music {
+            Some(music) => music.play(),
+            None => Ok(()),
         }
-        Err(ggez::error::GameError::SoundError)
+    }
+
+    // Muting keeps the music going silently, so it doesn't restart from the top
+    pub fn toggle_mute(&mut self) {
+        self.settings.is_muted = !self.settings.is_muted;
+        self.apply_volumes();
+        self.settings.save();
+    }
+
+    pub fn change_volume(&mut self, channel: Channel, amount: f32) {
+        let volume = self.settings.volume_mut(channel);
+        *volume = (*volume + amount).max(0.0).min(1.0);
+        self.apply_volumes();
+        self.settings.save();
+    }
+
+    pub fn volume_up(&mut self, channel: Channel) {
+        self.change_volume(channel, VOLUME_STEP);
+    }
+
+    pub fn volume_down(&mut self, channel: Channel) {
+        self.change_volume(channel, -VOLUME_STEP);
+    }
+
+    fn apply_volumes(&mut self) {
+        let sfx_volume = self.settings.output_volume(Channel::Sfx);
+        for source in self.sounds.values_mut() {
+            source.set_volume(sfx_volume);
+        }
+        if let Some(music) = &mut self.music {
+            music.set_volume(self.settings.output_volume(Channel::Music));
+        }
+    }
+
+    // Audio listener, one sound per gameplay event
+    pub fn on_event(&mut self, event: &GameEvent) {
+        let sound = match event {
+            GameEvent::PlayerStepped { .. } => Sound::PlayerWalk,
+            GameEvent::PlayerDug { .. } => Sound::PlayerDig,
+            GameEvent::PlayerKilled { .. } => Sound::PlayerHit,
+            GameEvent::Teleported { .. } => Sound::PlayerTeleport,
+            GameEvent::LevelCompleted => Sound::LevelCompleted,
+            GameEvent::SkeletonAttacking { .. } | GameEvent::SkeletonReleased { .. } => {
+                Sound::SkeletonAttack
+            }
+            GameEvent::ExitLocked => Sound::DoorLocked,
+            GameEvent::PlayerStartedFalling => Sound::PlayerFall,
+            GameEvent::Landed { .. } => Sound::PlayerLand,
+            _ => return,
+        };
+        let _ = self.play(sound);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<AudioSettings, String> {
+        text.parse::<AudioSettings>()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let settings = AudioSettings {
+            is_muted: true,
+            master_volume: 0.8,
+            sfx_volume: 0.25,
+            music_volume: 0.0,
+        };
+        let text = settings.to_string();
+        assert_eq!(
+            text,
+            "dig_escape_audio 1\nmuted 1\nmaster 0.8\nsfx 0.25\nmusic 0\n"
+        );
+        assert_eq!(parse(&text), Ok(settings));
+    }
+
+    #[test]
+    fn missing_lines_keep_the_defaults() {
+        let settings = parse("dig_escape_audio 1\nsfx 0.5\n").unwrap();
+        assert_eq!(
+            settings,
+            AudioSettings {
+                sfx_volume: 0.5,
+                ..AudioSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn volumes_are_clamped() {
+        let settings = parse("dig_escape_audio 1\nmaster 3\nmusic -1\n").unwrap();
+        assert_eq!(settings.master_volume, 1.0);
+        assert_eq!(settings.music_volume, 0.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("muted 0\n"),
+            Err("expected 'dig_escape_audio 1'".to_string())
+        );
+        assert_eq!(
+            parse("dig_escape_audio 1\nmuted yes\n"),
+            Err("line 2: can't read 'muted yes'".to_string())
+        );
+        assert_eq!(
+            parse("dig_escape_audio 1\nsfx\n"),
+            Err("line 2: can't read 'sfx'".to_string())
+        );
+        assert_eq!(
+            parse("dig_escape_audio 1\n\nvoice 1\n"),
+            Err("line 3: can't read 'voice 1'".to_string())
+        );
+    }
+
+    #[test]
+    fn output_volume() {
+        let mut settings = AudioSettings {
+            master_volume: 0.5,
+            ..AudioSettings::default()
+        };
+        assert_eq!(settings.output_volume(Channel::Sfx), 0.5);
+        assert_eq!(settings.output_volume(Channel::Music), 0.25);
+        settings.is_muted = true;
+        assert_eq!(settings.output_volume(Channel::Sfx), 0.0);
     }
 }
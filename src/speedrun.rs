@@ -0,0 +1,398 @@
+use crate::campaign::Campaign;
+use crate::{constantes, storage};
+use ggez::{graphics, Context, GameResult};
+use gwg as ggez;
+use nalgebra as na;
+use std::fmt;
+use std::str::FromStr;
+
+const SPLITS_HEADER: &str = "dig_escape_splits 1";
+const BEST_KEY: &str = "speedrun_best";
+const LAST_KEY: &str = "speedrun_last";
+
+const SPLITS_SHOWN: usize = 5; // most recent splits listed under the timer
+
+// In tiles
+const TIMER_HEIGHT: f32 = 0.5;
+const SPLIT_HEIGHT: f32 = 0.35;
+
+#[derive(Clone)]
+pub struct Split {
+    pub map_name: String,
+    pub title: String,
+    pub time: f32, // seconds since the run started, not since the previous split
+}
+
+// Splits of a whole run, saved as the personal best and exported after every finished run
+//
+// dig_escape_splits 1
+// split 5.120 /maps/map_first.txt Dig Out
+// split 18.034 /maps/map_1skeleton.txt Something Buried
+#[derive(Default, Clone)]
+pub struct Splits {
+    pub splits: Vec<Split>,
+}
+
+impl Splits {
+    pub fn total(&self) -> Option<f32> {
+        self.splits.last().map(|split| split.time)
+    }
+
+    // A personal best only counts for the campaign it was run on
+    fn matches(&self, campaign: &Campaign) -> bool {
+        self.splits.len() == campaign.len()
+            && self
+                .splits
+                .iter()
+                .zip(campaign.levels.iter())
+                .all(|(split, level)| split.map_name == level.map_name)
+    }
+}
+
+impl fmt::Display for Splits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", SPLITS_HEADER)?;
+        for split in self.splits.iter() {
+            writeln!(
+                f,
+                "split {:.3} {} {}",
+                split.time, split.map_name, split.title
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Splits {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == SPLITS_HEADER => {}
+            _ => return Err(format!("expected '{}'", SPLITS_HEADER)),
+        }
+
+        let mut splits = Splits::default();
+        for (line_index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid_line = || format!("line {}: can't read '{}'", line_index + 1, line);
+            let mut words = line.trim().splitn(4, ' ');
+            if words.next() != Some("split") {
+                return Err(invalid_line());
+            }
+            let time = words.next().and_then(|time| time.parse().ok());
+            let map_name = words.next();
+            match (time, map_name) {
+                (Some(time), Some(map_name)) => splits.splits.push(Split {
+                    map_name: map_name.to_string(),
+                    title: words.next().unwrap_or("").to_string(),
+                    time,
+                }),
+                _ => return Err(invalid_line()),
+            }
+        }
+        Ok(splits)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum RunState {
+    Waiting, // armed, the clock starts with the first move of the first level
+    Running,
+    Finished,
+}
+
+// One line of the timer, kept between frames and rebuilt only when the line changes
+struct LineText {
+    line: String,
+    text: graphics::Text,
+    height: f32,
+}
+
+// A timed run through the whole campaign, from the first move of the first level
+// to the exit of the last one. The clock only runs while a level is played or completed
+pub struct Speedrun {
+    pub state: RunState,
+    pub elapsed: f32,
+    pub splits: Splits,
+    pub personal_best: Option<Splits>,
+    pub is_personal_best: bool, // the finished run beat the previous best
+    line_texts: Vec<LineText>,  // the timer first, then the splits shown
+}
+
+impl Speedrun {
+    pub fn new(campaign: &Campaign) -> Self {
+        let personal_best = storage::load(BEST_KEY)
+            .and_then(|text| {
+                text.parse::<Splits>()
+                    .map_err(|e| eprintln!("could not read speedrun personal best: {}", e))
+                    .ok()
+            })
+            .filter(|best| best.matches(campaign));
+        Speedrun {
+            state: RunState::Waiting,
+            elapsed: 0.0,
+            splits: Splits::default(),
+            personal_best,
+            is_personal_best: false,
+            line_texts: vec![],
+        }
+    }
+
+    // The level the run continues with, playing anything else abandons the run
+    pub fn next_level(&self) -> usize {
+        self.splits.splits.len()
+    }
+
+    pub fn start(&mut self) {
+        if self.state == RunState::Waiting {
+            self.state = RunState::Running;
+        }
+    }
+
+    // is_timed is false on every screen but the level and its complete screen, pausing the clock
+    pub fn update(&mut self, delta: f32, is_timed: bool) {
+        if self.state == RunState::Running && is_timed {
+            self.elapsed += delta;
+        }
+    }
+
+    // Called when a level is completed, the last level stops the clock
+    pub fn split(&mut self, campaign: &Campaign, map_index: usize) {
+        if self.state != RunState::Running || map_index != self.next_level() {
+            return;
+        }
+        let level = &campaign.levels[map_index];
+        self.splits.splits.push(Split {
+            map_name: level.map_name.clone(),
+            title: level.title.clone(),
+            time: self.elapsed,
+        });
+        if self.splits.splits.len() >= campaign.len() {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.state = RunState::Finished;
+        let best_total = self.personal_best.as_ref().and_then(|best| best.total());
+        self.is_personal_best = best_total.map_or(true, |best_total| self.elapsed < best_total);
+
+        let text = self.splits.to_string();
+        if let Err(e) = storage::save(LAST_KEY, &text) {
+            eprintln!("could not export splits: {}", e);
+        }
+        if self.is_personal_best {
+            if let Err(e) = storage::save(BEST_KEY, &text) {
+                eprintln!("could not save speedrun personal best: {}", e);
+            }
+        }
+    }
+
+    // How far ahead (negative) or behind the personal best a split is
+    pub fn difference(&self, index: usize) -> Option<f32> {
+        let best = self.personal_best.as_ref()?.splits.get(index)?;
+        Some(self.splits.splits.get(index)?.time - best.time)
+    }
+}
+
+// 1:05.37
+pub fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+fn format_difference(seconds: f32) -> String {
+    let sign = if seconds < 0.0 { '-' } else { '+' };
+    format!("{}{:.2}", sign, seconds.abs())
+}
+
+// The text of a line from the cache, built and measured again only if the line changed
+fn line_text<'a>(
+    ctx: &mut Context,
+    font: graphics::Font,
+    line_texts: &'a mut Vec<LineText>,
+    index: usize,
+    line: String,
+) -> &'a LineText {
+    let cached = line_texts.get(index);
+    if cached.map_or(true, |cached| cached.line != line) {
+        let text = graphics::Text::new((line.as_str(), font, 40.0));
+        let (_text_w, text_h) = text.dimensions(ctx);
+        let line_text = LineText {
+            line,
+            text,
+            height: text_h,
+        };
+        if index < line_texts.len() {
+            line_texts[index] = line_text;
+        } else {
+            line_texts.push(line_text);
+        }
+    }
+    &line_texts[index]
+}
+
+// row and height in tiles, left aligned a bit in from the map edge
+fn draw_line(
+    ctx: &mut Context,
+    line_text: &LineText,
+    tile: f32,
+    row: f32,
+    height: f32,
+    color: graphics::Color,
+) -> GameResult {
+    let scale = height * tile / line_text.height;
+    let params = graphics::DrawParam::default()
+        .dest(na::Point2::new(0.15 * tile, row * tile))
+        .scale(na::Vector2::new(scale, scale))
+        .color(color);
+    graphics::draw(ctx, &line_text.text, params)
+}
+
+// The timer in the top left corner with the latest splits under it
+pub fn draw(
+    speedrun: &mut Speedrun,
+    ctx: &mut Context,
+    font: graphics::Font,
+    screen_size: &na::Point2<f32>,
+) -> GameResult {
+    let tile = screen_size.x;
+    let timer_color = match (speedrun.state, speedrun.is_personal_best) {
+        (RunState::Finished, true) => constantes::COLOR_SPLIT_AHEAD,
+        (RunState::Finished, false) => constantes::COLOR_SPLIT_BEHIND,
+        _ => constantes::COLOR_MENU,
+    };
+    let mut lines = vec![(format_time(speedrun.elapsed), timer_color)];
+
+    let splits = &speedrun.splits.splits;
+    let first_shown = splits.len().saturating_sub(SPLITS_SHOWN);
+    for (index, split) in splits.iter().enumerate().skip(first_shown) {
+        let mut line = format!("{}. {}", index + 1, format_time(split.time));
+        let color = match speedrun.difference(index) {
+            Some(difference) => {
+                line.push(' ');
+                line.push_str(&format_difference(difference));
+                match difference <= 0.0 {
+                    true => constantes::COLOR_SPLIT_AHEAD,
+                    false => constantes::COLOR_SPLIT_BEHIND,
+                }
+            }
+            None => constantes::COLOR_MENU,
+        };
+        lines.push((line, color));
+    }
+
+    for (index, (line, color)) in lines.into_iter().enumerate() {
+        let line_text = line_text(ctx, font, &mut speedrun.line_texts, index, line);
+        if index == 0 {
+            draw_line(ctx, line_text, tile, 0.1, TIMER_HEIGHT, color)?;
+        } else {
+            let row = 0.2 + TIMER_HEIGHT + (index - 1) as f32 * SPLIT_HEIGHT;
+            draw_line(ctx, line_text, tile, row, SPLIT_HEIGHT * 0.8, color)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign::parse_campaign;
+
+    #[test]
+    fn splits_roundtrip() {
+        let splits = Splits {
+            splits: vec![
+                Split {
+                    map_name: "/maps/map_first.txt".to_string(),
+                    title: "Dig Out".to_string(),
+                    time: 5.12,
+                },
+                Split {
+                    map_name: "/maps/map_1skeleton.txt".to_string(),
+                    title: "Something Buried".to_string(),
+                    time: 18.034,
+                },
+            ],
+        };
+        let text = splits.to_string();
+        assert_eq!(
+            text,
+            "dig_escape_splits 1\n\
+             split 5.120 /maps/map_first.txt Dig Out\n\
+             split 18.034 /maps/map_1skeleton.txt Something Buried\n"
+        );
+        let read = text.parse::<Splits>().unwrap();
+        assert_eq!(read.splits.len(), 2);
+        assert_eq!(read.splits[1].title, "Something Buried");
+        assert_eq!(read.total(), Some(18.034));
+    }
+
+    #[test]
+    fn split_without_title() {
+        let read = "dig_escape_splits 1\nsplit 1.5 /maps/a.txt\n"
+            .parse::<Splits>()
+            .unwrap();
+        assert_eq!(read.splits[0].title, "");
+        assert_eq!(Splits::default().total(), None);
+    }
+
+    #[test]
+    fn splits_errors() {
+        let error = |text: &str| text.parse::<Splits>().err().unwrap();
+        assert_eq!(
+            error("split 1 /maps/a.txt\n"),
+            "expected 'dig_escape_splits 1'"
+        );
+        assert_eq!(
+            error("dig_escape_splits 1\n\nsplit fast /maps/a.txt\n"),
+            "line 3: can't read 'split fast /maps/a.txt'"
+        );
+        assert_eq!(
+            error("dig_escape_splits 1\nsplit 1.0\n"),
+            "line 2: can't read 'split 1.0'"
+        );
+        assert_eq!(
+            error("dig_escape_splits 1\nlap 1.0 /maps/a.txt\n"),
+            "line 2: can't read 'lap 1.0 /maps/a.txt'"
+        );
+    }
+
+    #[test]
+    fn clock_runs_only_while_timed() {
+        let campaign = parse_campaign("a.txt | A | 3\nb.txt | B | 4\n").unwrap();
+        let mut run = Speedrun::new(&campaign);
+        run.update(1.0, true);
+        assert_eq!(run.elapsed, 0.0);
+
+        run.start();
+        run.update(1.0, true);
+        run.update(5.0, false);
+        run.update(0.5, true);
+        assert_eq!(run.elapsed, 1.5);
+
+        run.split(&campaign, 1);
+        assert!(run.splits.splits.is_empty());
+        run.split(&campaign, 0);
+        assert_eq!(run.splits.total(), Some(1.5));
+        assert_eq!(run.next_level(), 1);
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(format_time(0.0), "0:00.00");
+        assert_eq!(format_time(65.37), "1:05.37");
+        assert_eq!(format_time(-3.0), "0:00.00");
+        assert_eq!(format_difference(-1.234), "-1.23");
+        assert_eq!(format_difference(0.5), "+0.50");
+    }
+}
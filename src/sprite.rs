@@ -4,10 +4,94 @@ use nalgebra as na;
 
 use crate::constantes;
 use crate::util;
+use ggez::error::GameError;
+use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::{graphics, Context, GameResult};
 use graphics::DrawParam;
+use std::collections::HashMap;
+// What a sprite is drawn with, resolved by name in the atlas (resources/textures/atlas.txt)
+// Variants of a group wrap around the number of sprites in it, so the game doesn't need to
+// know how many there are
+// Random variants are picked below this, it divides evenly by any group size up to 8
+pub const VARIANT_RANGE: usize = 840;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Texture {
+    Player,
+    PlayerDig,
+    PlayerDead,
+    PlayerFall,
+    Grass,
+    Dirt,
+    SkeletonBlock,
+    SkeletonBlockDug,
+    Skeleton,
+    SkeletonAttack,
+    Teleporter,
+    Exit,
+    SoundOn,
+    SoundOff,
+    Straw(usize), // foilage that rotates
+    Bush(usize),  // foilage that stretches
+    Cloud(usize),
+}
+
+impl Texture {
+    // Sprites that have to be in the atlas, groups need at least one sprite
+    pub const NAMED: &'static [Texture] = &[
+        Texture::Player,
+        Texture::PlayerDig,
+        Texture::PlayerDead,
+        Texture::PlayerFall,
+        Texture::Grass,
+        Texture::Dirt,
+        Texture::SkeletonBlock,
+        Texture::SkeletonBlockDug,
+        Texture::Skeleton,
+        Texture::SkeletonAttack,
+        Texture::Teleporter,
+        Texture::Exit,
+        Texture::SoundOn,
+        Texture::SoundOff,
+    ];
+    pub const GROUPS: &'static [&'static str] = &["straw", "bush", "cloud"];
+
+    // Name in the atlas, or the group for variants
+    pub fn name(self) -> &'static str {
+        match self {
+            Texture::Player => "player",
+            Texture::PlayerDig => "player_dig",
+            Texture::PlayerDead => "player_dead",
+            Texture::PlayerFall => "player_fall",
+            Texture::Grass => "ground",
+            Texture::Dirt => "ground_below",
+            Texture::SkeletonBlock => "buried",
+            Texture::SkeletonBlockDug => "unburied",
+            Texture::Skeleton => "skeleton_neutral",
+            Texture::SkeletonAttack => "skeleton_attack",
+            Texture::Teleporter => "blue_door",
+            Texture::Exit => "red_door",
+            Texture::SoundOn => "sound_on",
+            Texture::SoundOff => "sound_off",
+            Texture::Straw(_) => "straw",
+            Texture::Bush(_) => "bush",
+            Texture::Cloud(_) => "cloud",
+        }
+    }
+
+    pub fn variant(self) -> Option<usize> {
+        match self {
+            Texture::Straw(variant) | Texture::Bush(variant) | Texture::Cloud(variant) => {
+                Some(variant)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct SpriteComponent {
-    pub texture_index: usize,
+    pub texture: Texture,
     pub scale: na::Vector2<f32>,
     pub is_flipped: bool,
     pub visual_position: na::Point2<f32>,
@@ -17,7 +101,7 @@ pub struct SpriteComponent {
 impl Default for SpriteComponent {
     fn default() -> Self {
         SpriteComponent {
-            texture_index: 0,
+            texture: Texture::Player,
             scale: na::Vector2::new(1.0, 1.0),
             is_flipped: false,
             visual_position: na::Point2::new(0.0, 0.0),
@@ -25,18 +109,137 @@ impl Default for SpriteComponent {
         }
     }
 }
+
+// A sprite in the atlas, src is in texture coordinates (0 to 1)
+#[derive(Clone, Debug)]
+pub struct AtlasRegion {
+    pub name: String,
+    pub group: Option<String>,
+    pub src: graphics::Rect,
+}
+
+// Every sprite lives in one atlas image and is queued in one sprite batch,
+// draw() sends everything queued since the last draw in a single call
 pub struct SpriteCollection {
-    pub images: [graphics::Image; 21],
+    pub atlas: graphics::Image,
+    pub regions: Vec<AtlasRegion>,
+    pub names: HashMap<String, usize>,
+    pub groups: HashMap<String, Vec<usize>>,
+    batch: SpriteBatch,
 }
 
 impl SpriteCollection {
-    pub fn get_sprite<'a, 'b: 'a>(&'a self, index: usize) -> Option<&'a graphics::Image> {
-        self.images.get(index)
+    // The atlas image and data named in the asset manifest
+    pub fn load(mut atlas: graphics::Image, atlas_data: &str) -> GameResult<SpriteCollection> {
+        atlas.set_filter(graphics::FilterMode::Nearest);
+        let atlas_size = na::Vector2::new(atlas.width() as f32, atlas.height() as f32);
+        let regions = parse_atlas(atlas_data, &atlas_size).map_err(load_error)?;
+        SpriteCollection::new(atlas, regions).map_err(load_error)
+    }
+
+    // Fails if a sprite the game needs isn't in the regions
+    pub fn new(atlas: graphics::Image, regions: Vec<AtlasRegion>) -> Result<Self, String> {
+        let mut names = HashMap::new();
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, region) in regions.iter().enumerate() {
+            names.insert(region.name.clone(), index);
+            if let Some(group) = &region.group {
+                groups.entry(group.clone()).or_default().push(index);
+            }
+        }
+        let missing: Vec<&str> = Texture::NAMED
+            .iter()
+            .map(|texture| texture.name())
+            .filter(|name| !names.contains_key(*name))
+            .chain(
+                Texture::GROUPS
+                    .iter()
+                    .copied()
+                    .filter(|group| !groups.contains_key(*group)),
+            )
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("no sprite for {}", missing.join(", ")));
+        }
+        Ok(SpriteCollection {
+            batch: SpriteBatch::new(atlas.clone()),
+            atlas,
+            regions,
+            names,
+            groups,
+        })
+    }
+
+    // The sprites of a group, in the order they are listed
+    pub fn group(&self, name: &str) -> &[usize] {
+        self.groups.get(name).map_or(&[], |group| group.as_slice())
+    }
+
+    pub fn region(&self, texture: Texture) -> &AtlasRegion {
+        let index = match texture.variant() {
+            Some(variant) => {
+                let group = self.group(texture.name());
+                group[variant % group.len()]
+            }
+            None => self.names[texture.name()],
+        };
+        &self.regions[index]
+    }
+
+    // Params are the same as drawing the sprite as its own image
+    pub fn add(&mut self, texture: Texture, params: DrawParam) {
+        let src = self.region(texture).src;
+        self.batch.add(params.src(src));
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::draw(ctx, &self.batch, DrawParam::default())?;
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+fn load_error(message: String) -> GameError {
+    GameError::ResourceLoadError(format!("sprite atlas: {}", message))
+}
+
+// One sprite per line, name x y width height [group], in pixels
+pub fn parse_atlas(text: &str, atlas_size: &na::Vector2<f32>) -> Result<Vec<AtlasRegion>, String> {
+    let mut regions = vec![];
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid_line = || format!("line {}: can't read '{}'", line_index + 1, line);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() < 5 || words.len() > 6 {
+            return Err(invalid_line());
+        }
+        let mut numbers = [0.0; 4];
+        for (number, word) in numbers.iter_mut().zip(&words[1..5]) {
+            *number = word.parse::<f32>().map_err(|_| invalid_line())?;
+        }
+        let [x, y, width, height] = numbers;
+        if x + width > atlas_size.x || y + height > atlas_size.y {
+            return Err(format!("line {}: {} is outside the atlas", line_index + 1, words[0]));
+        }
+        regions.push(AtlasRegion {
+            name: words[0].to_string(),
+            group: words.get(5).map(|group| group.to_string()),
+            src: graphics::Rect::new(
+                x / atlas_size.x,
+                y / atlas_size.y,
+                width / atlas_size.x,
+                height / atlas_size.y,
+            ),
+        });
     }
+    Ok(regions)
 }
 
 pub fn render(
-    sprite_collection: &SpriteCollection,
+    sprite_collection: &mut SpriteCollection,
     ctx: &mut Context,
     transform_component: &TransformComponent,
     sprite: &mut SpriteComponent,
@@ -85,10 +288,6 @@ pub fn render(
         params = params.color(new_color);
     }
 
-    let image = sprite_collection
-        .images
-        .get(sprite.texture_index)
-        .expect("No image with id...");
-    graphics::draw(ctx, image, params)?;
+    sprite_collection.add(sprite.texture, params);
     Ok(())
 }
@@ -1,2 +1,10 @@
+pub mod credits;
+pub mod editor;
 pub mod game_state;
+pub mod level_complete;
+pub mod level_select;
 pub mod main_state;
+pub mod menu;
+pub mod pause;
+pub mod screen;
+pub mod title;
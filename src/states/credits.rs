@@ -0,0 +1,58 @@
+use crate::states::main_state::MainState;
+use crate::{constantes, util};
+use ggez::{graphics, Context, GameResult};
+use gwg as ggez;
+
+const CREDITS: &[&str] = &[
+    "Game by TanTanDev",
+    "Made with Rust and good-web-game",
+    "Fonts by Kenney (CC0)",
+    "Thanks for playing!",
+];
+
+pub struct CreditsScreen {
+    pub all_levels_completed_text: graphics::Text,
+    pub lines: Vec<graphics::Text>,
+}
+
+impl CreditsScreen {
+    pub fn new(font: graphics::Font) -> Self {
+        CreditsScreen {
+            all_levels_completed_text: graphics::Text::new((
+                "You completed ALL LEVELS!",
+                font,
+                60.0,
+            )),
+            lines: CREDITS
+                .iter()
+                .map(|line| graphics::Text::new((*line, font, 40.0)))
+                .collect(),
+        }
+    }
+}
+
+// Any key or tap goes back to the title
+pub fn key_down(main_state: &mut MainState) {
+    main_state.return_to_title();
+}
+
+pub fn mouse_up(main_state: &mut MainState) {
+    main_state.return_to_title();
+}
+
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let map_size = main_state.game_state.map_size;
+    let color = constantes::COLOR_MENU;
+    let credits = &main_state.credits;
+    let campaign = &main_state.campaign;
+    if !campaign.is_empty() && main_state.progress.highest_level(campaign) >= campaign.len() {
+        let text = &credits.all_levels_completed_text;
+        util::render_text_row(text, ctx, &screen_size, &map_size, 0.8, 1.0, color)?;
+    }
+    for (index, line) in credits.lines.iter().enumerate() {
+        let row = 2.5 + index as f32 * 1.0;
+        util::render_text_row(line, ctx, &screen_size, &map_size, row, 0.6, color)?;
+    }
+    Ok(())
+}
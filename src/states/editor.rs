@@ -0,0 +1,285 @@
+use crate::generator::{self, Difficulty, GeneratorError, GeneratorSettings};
+use crate::map::{self, MapData, Tile};
+use crate::states::main_state::{self, MainState};
+use crate::{constantes, util};
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+use nalgebra as na;
+
+// Map tiles painted with the mouse or the keyboard, test played without leaving the game
+// and saved in the same text format the campaign maps use
+pub struct EditorScreen {
+    pub size: na::Point2<i32>,
+    pub tiles: Vec<Option<Tile>>,
+    pub cursor: na::Point2<i32>,
+    pub brush: Option<Tile>, // None erases
+    pub font: graphics::Font,
+    pub help_text: graphics::Text,
+    pub status_text: graphics::Text,
+}
+
+impl EditorScreen {
+    // Starts from the last saved map if there is one
+    pub fn new(font: graphics::Font) -> Self {
+        let size = na::Point2::new(constantes::DEFAULT_MAP_WIDTH, constantes::DEFAULT_MAP_HEIGHT);
+        let mut editor = EditorScreen {
+            size,
+            tiles: vec![None; (size.x * size.y) as usize],
+            cursor: na::Point2::new(0, 0),
+            brush: Some(Tile::Grass),
+            font,
+            help_text: graphics::Text::new(("", font, 40.0)),
+            status_text: graphics::Text::new((" ", font, 40.0)),
+        };
+        editor.update_help();
+        if let Ok(text) = std::fs::read_to_string(constantes::EDITOR_MAP_PATH) {
+            match map::parse_map(&text) {
+                Ok(map_data) => editor.set_map(&map_data),
+                Err(e) => editor.set_status(&format!("{}: {}", constantes::EDITOR_MAP_PATH, e)),
+            }
+        }
+        editor
+    }
+
+    // Never smaller than the default size, so there is room to paint, and never bigger than a map can be
+    fn set_map(&mut self, map_data: &MapData) {
+        let clamp = |n: i32, default: i32| n.max(default).min(constantes::MAX_MAP_SIZE);
+        self.size = na::Point2::new(
+            clamp(map_data.width, constantes::DEFAULT_MAP_WIDTH),
+            clamp(map_data.height, constantes::DEFAULT_MAP_HEIGHT),
+        );
+        self.tiles = vec![None; (self.size.x * self.size.y) as usize];
+        for (position, tile) in map_data.tiles.iter() {
+            self.set_tile(position, Some(*tile));
+        }
+        self.cursor = na::Point2::new(
+            self.cursor.x.min(self.size.x - 1),
+            self.cursor.y.min(self.size.y - 1),
+        );
+    }
+
+    fn index(&self, position: &na::Point2<i32>) -> Option<usize> {
+        if position.x < 0 || position.y < 0 || position.x >= self.size.x || position.y >= self.size.y
+        {
+            return None;
+        }
+        Some((position.y * self.size.x + position.x) as usize)
+    }
+
+    pub fn tile(&self, position: &na::Point2<i32>) -> Option<Tile> {
+        self.index(position).and_then(|index| self.tiles[index])
+    }
+
+    // There is only one player and one exit, painting them moves them
+    pub fn set_tile(&mut self, position: &na::Point2<i32>, tile: Option<Tile>) {
+        if let Some(unique @ Tile::Player) | Some(unique @ Tile::Exit) = tile {
+            for other in self.tiles.iter_mut() {
+                if *other == Some(unique) {
+                    *other = None;
+                }
+            }
+        }
+        if let Some(index) = self.index(position) {
+            self.tiles[index] = tile;
+        }
+    }
+
+    // Painting a tile with what it already holds clears it
+    fn paint(&mut self, position: &na::Point2<i32>) {
+        let tile = match self.tile(position) == self.brush {
+            true => None,
+            false => self.brush,
+        };
+        self.set_tile(position, tile);
+    }
+
+    pub fn map_data(&self) -> MapData {
+        let mut tiles = vec![];
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let position = na::Point2::new(x, y);
+                if let Some(tile) = self.tile(&position) {
+                    tiles.push((position, tile));
+                }
+            }
+        }
+        MapData {
+            width: self.size.x,
+            height: self.size.y,
+            tiles,
+        }
+    }
+
+    // The map as the game would read it, so test playing catches the same errors loading does
+    pub fn parse(&self) -> Result<MapData, map::MapError> {
+        map::parse_map(&map::map_to_string(&self.map_data()))
+    }
+
+    fn save(&mut self) {
+        let text = map::map_to_string(&self.map_data());
+        let path = std::path::Path::new(constantes::EDITOR_MAP_PATH);
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, text));
+        match result {
+            Ok(()) => self.set_status(&format!("Saved {}", constantes::EDITOR_MAP_PATH)),
+            Err(e) => self.set_status(&format!("{}: {}", constantes::EDITOR_MAP_PATH, e)),
+        }
+    }
+
+    pub fn set_status(&mut self, message: &str) {
+        self.status_text = graphics::Text::new((message, self.font, 40.0));
+    }
+
+    fn update_help(&mut self) {
+        let brush = match self.brush {
+            Some(tile) => format!("{} {}", tile.to_char(), tile_name(tile)),
+            None => "- erase".to_string(),
+        };
+        let help = format!(
+            "Brush {}   0-5 brush, - erase, 7-9 random, T test, F2 save",
+            brush
+        );
+        self.help_text = graphics::Text::new((help, self.font, 40.0));
+    }
+}
+
+fn tile_name(tile: Tile) -> &'static str {
+    match tile {
+        Tile::Player => "player",
+        Tile::Grass => "grass",
+        Tile::SkeletonBlock => "skeleton",
+        Tile::Teleporter => "teleporter",
+        Tile::Exit => "exit",
+        Tile::Dirt => "dirt",
+    }
+}
+
+fn test_play(main_state: &mut MainState, ctx: &mut Context) {
+    match main_state.editor.parse() {
+        Ok(map_data) => {
+            main_state.editor.set_status(" ");
+            main_state.test_map(ctx, &map_data);
+        }
+        Err(e) => main_state.editor.set_status(&e.to_string()),
+    }
+}
+
+// Replaces the map with a random one that is known to be winnable
+fn generate(main_state: &mut MainState, ctx: &mut Context, difficulty: Difficulty) {
+    let seed = main_state::new_seed(ctx);
+    let message = match generator::generate(&GeneratorSettings::for_editor(difficulty), seed) {
+        Ok(generated) => {
+            main_state.editor.set_map(&generated.map_data);
+            main_state.fit_editor(ctx);
+            format!(
+                "Random {} level, shortest solution {} moves",
+                difficulty.name(),
+                generated.solution.len()
+            )
+        }
+        Err(GeneratorError::NotFound(_)) => {
+            format!("No {} level found, try again", difficulty.name())
+        }
+        Err(e) => e.to_string(),
+    };
+    main_state.editor.set_status(&message);
+}
+
+pub fn key_down(main_state: &mut MainState, ctx: &mut Context, keycode: KeyCode) {
+    let editor = &mut main_state.editor;
+    let mut cursor = editor.cursor;
+    match keycode {
+        KeyCode::Left | KeyCode::A => cursor.x -= 1,
+        KeyCode::Right | KeyCode::D => cursor.x += 1,
+        KeyCode::Up | KeyCode::W => cursor.y -= 1,
+        KeyCode::Down | KeyCode::S => cursor.y += 1,
+        KeyCode::Key0 => editor.brush = Some(Tile::Player),
+        KeyCode::Key1 => editor.brush = Some(Tile::Grass),
+        KeyCode::Key2 => editor.brush = Some(Tile::SkeletonBlock),
+        KeyCode::Key3 => editor.brush = Some(Tile::Teleporter),
+        KeyCode::Key4 => editor.brush = Some(Tile::Exit),
+        KeyCode::Key5 => editor.brush = Some(Tile::Dirt),
+        KeyCode::Minus | KeyCode::Delete | KeyCode::Backspace => editor.brush = None,
+        KeyCode::Space | KeyCode::Enter => {
+            let position = editor.cursor;
+            editor.paint(&position);
+        }
+        KeyCode::Key7 => return generate(main_state, ctx, Difficulty::Easy),
+        KeyCode::Key8 => return generate(main_state, ctx, Difficulty::Medium),
+        KeyCode::Key9 => return generate(main_state, ctx, Difficulty::Hard),
+        KeyCode::T => return test_play(main_state, ctx),
+        KeyCode::F2 => editor.save(),
+        KeyCode::Escape => return main_state.pop_screen(),
+        _ => {}
+    }
+    editor.cursor = na::Point2::new(
+        cursor.x.max(0).min(editor.size.x - 1),
+        cursor.y.max(0).min(editor.size.y - 1),
+    );
+    editor.update_help();
+}
+
+pub fn mouse_up(main_state: &mut MainState, ctx: &mut Context, x: f32, y: f32) {
+    let position = util::window_to_tiles(ctx, &main_state.screen_size, x, y);
+    let tile = na::Point2::new(position.x.floor() as i32, position.y.floor() as i32);
+    let editor = &mut main_state.editor;
+    if editor.index(&tile).is_some() {
+        editor.cursor = tile;
+        editor.paint(&tile);
+    }
+}
+
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let tile_size = screen_size.x;
+    let editor = &main_state.editor;
+    let sprite_collection = &mut main_state.sprite_collection;
+
+    let scale = tile_size / 16.0;
+    for y in 0..editor.size.y {
+        for x in 0..editor.size.x {
+            let position = na::Point2::new(x, y);
+            let dest = na::Point2::new(x as f32, y as f32) * tile_size;
+            if let Some(tile) = editor.tile(&position) {
+                let params = graphics::DrawParam::default()
+                    .dest(dest)
+                    .scale(na::Vector2::new(scale, scale));
+                sprite_collection.add(tile.texture(), params);
+            }
+        }
+    }
+    sprite_collection.draw(ctx)?;
+
+    for y in 0..editor.size.y {
+        for x in 0..editor.size.x {
+            let dest = na::Point2::new(x as f32, y as f32) * tile_size;
+            let rect = graphics::Rect::new(dest.x, dest.y, tile_size, tile_size);
+            let mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(1.0),
+                rect,
+                constantes::COLOR_EDITOR_GRID,
+            )?;
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        }
+    }
+
+    let cursor = na::convert::<na::Point2<i32>, na::Point2<f32>>(editor.cursor) * tile_size;
+    let rect = graphics::Rect::new(cursor.x, cursor.y, tile_size, tile_size);
+    let mesh = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::stroke(3.0),
+        rect,
+        constantes::COLOR_MENU_SELECTED,
+    )?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+
+    let size = editor.size;
+    let color = constantes::COLOR_MENU;
+    util::render_text_row(&editor.help_text, ctx, &screen_size, &size, 0.05, 0.35, color)?;
+    let status_row = size.y as f32 - 0.45;
+    util::render_text_row(&editor.status_text, ctx, &screen_size, &size, status_row, 0.35, color)
+}
@@ -1,15 +1,16 @@
+use crate::constantes;
 use crate::entities::player::Player;
 use crate::entities::{
     cloud, foilage, skeleton,
     teleporter::{Exit, Teleporter},
 };
+use crate::grid::OccupancyGrid;
 use cloud::Cloud;
 use foilage::{Foilage, Grass};
-use ggez::{graphics, Context};
-use gwg as ggez;
 use nalgebra as na;
 use skeleton::{Skeleton, SkeletonBlock};
 
+#[derive(Clone)]
 pub struct GameState {
     pub player: Player,
     pub grasses: Vec<Grass>,
@@ -19,26 +20,20 @@ pub struct GameState {
     pub clouds: Vec<Cloud>,
     pub teleporters: [Option<Teleporter>; 2],
     pub exit: Exit,
-    pub map_size: na::Point2<f32>,
-    pub game_over_text: ggez::graphics::Text,
-    pub all_levels_completed_text: ggez::graphics::Text,
-    pub is_all_levels_completed: bool,
+    pub map_size: na::Point2<i32>, // width and height in tiles
+    pub grid: OccupancyGrid,
+    pub turn: u32,
+    pub move_count: u32, // turns the player acted on this attempt, leaving through the exit aside
 }
 
 impl GameState {
-    pub fn new(ctx: &mut Context) -> GameState {
-        let font = graphics::Font::new(ctx, "kenny_fontpackage/Fonts/Kenney Mini.ttf").unwrap();
-        let game_over_text = graphics::Text::new(("PRESS (R) to restart!", font, 60.0));
-        let all_levels_completed_text = graphics::Text::new((
-            "You completed ALL LEVELS! Press R to play again",
-            font,
-            30.0,
-        ));
-
+    pub fn new() -> GameState {
+        let map_size = na::Point2::new(constantes::DEFAULT_MAP_WIDTH, constantes::DEFAULT_MAP_HEIGHT);
         GameState {
-            game_over_text,
-            all_levels_completed_text,
-            map_size: na::Point2::new(0.0, 0.0),
+            map_size,
+            grid: OccupancyGrid::new(map_size),
+            turn: 0,
+            move_count: 0,
             player: Player::default(),
             grasses: vec![],
             skeleton_blocks: vec![],
@@ -47,7 +42,6 @@ impl GameState {
             clouds: vec![],
             teleporters: [None, None],
             exit: Exit::default(),
-            is_all_levels_completed: false,
         }
     }
 }
@@ -0,0 +1,113 @@
+use crate::states::main_state::MainState;
+use crate::states::menu::{Menu, MenuInput};
+use crate::states::screen::Screen;
+use crate::{constantes, progress, util};
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+
+#[derive(Clone, Copy)]
+pub enum LevelCompleteAction {
+    Next,
+    PlayAgain,
+    LevelSelect,
+}
+
+pub struct LevelCompleteScreen {
+    pub font: graphics::Font,
+    pub complete_text: graphics::Text,
+    pub level_text: graphics::Text,
+    pub moves_text: graphics::Text,
+    pub stars: Option<u8>, // None if the level has no par
+    pub menu: Menu<LevelCompleteAction>,
+}
+
+impl LevelCompleteScreen {
+    pub fn new(font: graphics::Font) -> Self {
+        let entries = [
+            (LevelCompleteAction::Next, "Next level"),
+            (LevelCompleteAction::PlayAgain, "Play again"),
+            (LevelCompleteAction::LevelSelect, "Levels"),
+        ];
+        LevelCompleteScreen {
+            font,
+            complete_text: graphics::Text::new(("LEVEL COMPLETE!", font, 60.0)),
+            level_text: graphics::Text::new(("", font, 40.0)),
+            moves_text: graphics::Text::new(("", font, 40.0)),
+            stars: None,
+            menu: Menu::new(font, &entries, 4.5),
+        }
+    }
+
+    // Summary of the level that was just completed
+    pub fn set_result(
+        &mut self,
+        title: &str,
+        moves: u32,
+        par: Option<u32>,
+        best_moves: u32,
+        is_new_best: bool,
+        hints: u32,
+    ) {
+        self.level_text = graphics::Text::new((title, self.font, 40.0));
+        let mut moves_line = match par {
+            Some(par) => format!("{} moves, par {}", moves, par),
+            None => format!("{} moves", moves),
+        };
+        match is_new_best {
+            true => moves_line.push_str(", new best!"),
+            false => moves_line.push_str(&format!(", best {}", best_moves)),
+        }
+        if hints > 0 {
+            moves_line.push_str(&format!(", {} hints", hints));
+        }
+        self.stars = par.map(|par| progress::star_rating(moves, par));
+        self.moves_text = graphics::Text::new((moves_line, self.font, 40.0));
+        self.menu.selected = 0;
+    }
+}
+
+fn activate(main_state: &mut MainState, ctx: &mut Context, action: LevelCompleteAction) {
+    match action {
+        LevelCompleteAction::Next => main_state.next_level(ctx),
+        LevelCompleteAction::PlayAgain => {
+            main_state.pop_screen();
+            main_state.restart_current_map(ctx);
+        }
+        LevelCompleteAction::LevelSelect => {
+            main_state.return_to_title();
+            main_state.push_screen(Screen::LevelSelect);
+        }
+    }
+}
+
+pub fn key_down(main_state: &mut MainState, ctx: &mut Context, keycode: KeyCode) {
+    match main_state.level_complete.menu.key_down(keycode) {
+        MenuInput::Activate(action) => activate(main_state, ctx, action),
+        MenuInput::Back => activate(main_state, ctx, LevelCompleteAction::LevelSelect),
+        MenuInput::None => {}
+    }
+}
+
+pub fn mouse_up(main_state: &mut MainState, ctx: &mut Context, x: f32, y: f32) {
+    let position = util::window_to_tiles(ctx, &main_state.screen_size, x, y);
+    if let MenuInput::Activate(action) = main_state.level_complete.menu.mouse_up(position) {
+        activate(main_state, ctx, action);
+    }
+}
+
+// Drawn on top of the completed level
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let map_size = main_state.game_state.map_size;
+    let screen = &main_state.level_complete;
+    let color = constantes::COLOR_MENU;
+    util::render_overlay(ctx, &screen_size, &map_size)?;
+    util::render_text_row(&screen.complete_text, ctx, &screen_size, &map_size, 0.4, 1.0, color)?;
+    if let Some(stars) = screen.stars {
+        util::render_stars(ctx, &screen_size, &map_size, 1.5, 0.8, stars, 3)?;
+    }
+    util::render_text_row(&screen.level_text, ctx, &screen_size, &map_size, 2.5, 0.6, color)?;
+    util::render_text_row(&screen.moves_text, ctx, &screen_size, &map_size, 3.3, 0.5, color)?;
+    screen.menu.draw(ctx, &screen_size, &map_size)
+}
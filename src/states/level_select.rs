@@ -0,0 +1,315 @@
+use crate::campaign::Campaign;
+use crate::entities::player::PlayerInputIntent;
+use crate::sprite::{SpriteCollection, Texture};
+use crate::states::game_state::GameState;
+use crate::states::main_state::MainState;
+use crate::transform_compontent::TransformComponent;
+use crate::{constantes, map, util};
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+use nalgebra as na;
+
+// Levels per page
+const COLUMNS: usize = 3;
+const ROWS: usize = 2;
+const LEVELS_PER_PAGE: usize = COLUMNS * ROWS;
+
+// In tiles
+const HEADER_HEIGHT: f32 = 1.2;
+const FOOTER_HEIGHT: f32 = 0.8;
+const CELL_PADDING: f32 = 0.2;
+const NAME_HEIGHT: f32 = 0.5;
+
+// Levels past the furthest one reached that can already be played,
+// so one hard level doesn't lock away the rest of the campaign
+const UNLOCKED_AHEAD: usize = 2;
+
+pub struct LevelEntry {
+    pub name_text: graphics::Text,
+    pub thumbnail: Option<GameState>, // None if the map can't be loaded
+}
+
+pub struct LevelSelectScreen {
+    pub header_text: graphics::Text,
+    pub previous_text: graphics::Text,
+    pub next_text: graphics::Text,
+    pub page_texts: Vec<graphics::Text>,
+    pub levels: Vec<LevelEntry>,
+    pub selected: usize,
+}
+
+impl LevelSelectScreen {
+    pub fn new(ctx: &mut Context, font: graphics::Font, campaign: &Campaign) -> Self {
+        let levels: Vec<LevelEntry> = campaign
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(index, level)| {
+                // Same tiles the level is built from when it's played
+                let thumbnail = map::read_map(ctx, &level.map_name).ok().map(|map_data| {
+                    let mut game_state = GameState::new();
+                    map::build_map(&mut game_state, &map_data);
+                    game_state
+                });
+                let name = format!("{}. {}", index + 1, level.title);
+                LevelEntry {
+                    name_text: graphics::Text::new((name, font, 40.0)),
+                    thumbnail,
+                }
+            })
+            .collect();
+        let page_count = page_count(levels.len());
+        LevelSelectScreen {
+            header_text: graphics::Text::new(("LEVELS", font, 60.0)),
+            previous_text: graphics::Text::new(("<", font, 40.0)),
+            next_text: graphics::Text::new((">", font, 40.0)),
+            page_texts: (1..=page_count)
+                .map(|page| graphics::Text::new((format!("{}/{}", page, page_count), font, 40.0)))
+                .collect(),
+            levels,
+            selected: 0,
+        }
+    }
+}
+
+fn page_count(level_count: usize) -> usize {
+    ((level_count + LEVELS_PER_PAGE - 1) / LEVELS_PER_PAGE).max(1)
+}
+
+fn is_unlocked(main_state: &MainState, index: usize) -> bool {
+    index <= main_state.progress.highest_level(&main_state.campaign) + UNLOCKED_AHEAD
+}
+
+fn play_selected(main_state: &mut MainState, ctx: &mut Context) {
+    let selected = main_state.level_select.selected;
+    if is_unlocked(main_state, selected) {
+        main_state.play_level(ctx, selected);
+    }
+}
+
+fn change_page(main_state: &mut MainState, forward: bool) {
+    let level_count = main_state.level_select.levels.len();
+    let page = main_state.level_select.selected / LEVELS_PER_PAGE;
+    let new_page = match forward {
+        true => (page + 1).min(page_count(level_count) - 1),
+        false => page.saturating_sub(1),
+    };
+    if new_page != page {
+        main_state.level_select.selected = new_page * LEVELS_PER_PAGE;
+    }
+}
+
+// Top left corner and size of a level cell on the current page, in tiles
+fn cell_rect(map_size: &na::Point2<i32>, index_on_page: usize) -> graphics::Rect {
+    let w = map_size.x as f32 / COLUMNS as f32;
+    let h = (map_size.y as f32 - HEADER_HEIGHT - FOOTER_HEIGHT) / ROWS as f32;
+    let column = (index_on_page % COLUMNS) as f32;
+    let row = (index_on_page / COLUMNS) as f32;
+    graphics::Rect::new(column * w, HEADER_HEIGHT + row * h, w, h)
+}
+
+pub fn key_down(main_state: &mut MainState, ctx: &mut Context, keycode: KeyCode) {
+    let level_count = main_state.level_select.levels.len();
+    if level_count == 0 || keycode == KeyCode::Escape {
+        main_state.pop_screen();
+        return;
+    }
+    let selected = main_state.level_select.selected;
+    main_state.level_select.selected = match keycode {
+        KeyCode::Left | KeyCode::A => selected.saturating_sub(1),
+        KeyCode::Right | KeyCode::D => (selected + 1).min(level_count - 1),
+        KeyCode::Up | KeyCode::W => selected.saturating_sub(COLUMNS),
+        KeyCode::Down | KeyCode::S => (selected + COLUMNS).min(level_count - 1),
+        _ => selected,
+    };
+    if keycode == KeyCode::Enter || keycode == KeyCode::Space {
+        play_selected(main_state, ctx);
+    }
+}
+
+pub fn mouse_up(main_state: &mut MainState, ctx: &mut Context, x: f32, y: f32) {
+    // Swipe to turn pages, the page follows the finger
+    let delta = na::Vector2::new(x, y) - main_state.mouse_pos_down;
+    match util::swipe_direction(delta) {
+        PlayerInputIntent::Left => return change_page(main_state, true),
+        PlayerInputIntent::Right => return change_page(main_state, false),
+        PlayerInputIntent::None => {}
+        _ => return,
+    }
+
+    let position = util::window_to_tiles(ctx, &main_state.screen_size, x, y);
+    let map_size = main_state.game_state.map_size;
+    let footer_row = map_size.y as f32 - FOOTER_HEIGHT;
+    if position.y >= footer_row {
+        let third = map_size.x as f32 / 3.0;
+        if position.x < third {
+            change_page(main_state, false);
+        } else if position.x > third * 2.0 {
+            change_page(main_state, true);
+        }
+        return;
+    }
+
+    let page_start = main_state.level_select.selected / LEVELS_PER_PAGE * LEVELS_PER_PAGE;
+    for index_on_page in 0..LEVELS_PER_PAGE {
+        let index = page_start + index_on_page;
+        if index >= main_state.level_select.levels.len() {
+            break;
+        }
+        let rect = cell_rect(&map_size, index_on_page);
+        if rect.contains(position) {
+            main_state.level_select.selected = index;
+            play_selected(main_state, ctx);
+            return;
+        }
+    }
+}
+
+// Every map tile drawn small, no animation or decoration
+fn render_thumbnail(
+    thumbnail: &GameState,
+    sprite_collection: &mut SpriteCollection,
+    ctx: &mut Context,
+    origin: na::Point2<f32>,
+    tile_size: f32,
+    color: graphics::Color,
+) -> GameResult {
+    let mut tiles: Vec<(&TransformComponent, Texture)> = vec![];
+    for grass in thumbnail.grasses.iter() {
+        tiles.push((&grass.transform, grass.sprite.texture));
+    }
+    for block in thumbnail.skeleton_blocks.iter() {
+        tiles.push((&block.transform, block.sprite.texture));
+    }
+    for teleporter in thumbnail.teleporters.iter().flatten() {
+        tiles.push((&teleporter.transform, teleporter.sprite.texture));
+    }
+    tiles.push((&thumbnail.exit.transform, thumbnail.exit.sprite.texture));
+    tiles.push((&thumbnail.player.transform, Texture::Player));
+
+    let scale = tile_size / 16.0;
+    for (transform, texture) in tiles {
+        let position = na::convert::<na::Point2<i32>, na::Point2<f32>>(transform.position);
+        let params = graphics::DrawParam::default()
+            .dest(origin + position.coords * tile_size)
+            .scale(na::Vector2::new(scale, scale))
+            .color(color);
+        sprite_collection.add(texture, params);
+    }
+    sprite_collection.draw(ctx)
+}
+
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let map_size = main_state.game_state.map_size;
+    let tile = screen_size.x;
+    let screen = &main_state.level_select;
+    let white = constantes::COLOR_MENU;
+    util::render_text_row(&screen.header_text, ctx, &screen_size, &map_size, 0.1, 1.0, white)?;
+
+    let page = screen.selected / LEVELS_PER_PAGE;
+    let page_start = page * LEVELS_PER_PAGE;
+    let page_end = (page_start + LEVELS_PER_PAGE).min(screen.levels.len());
+    for index in page_start..page_end {
+        let entry = &screen.levels[index];
+        let rect = cell_rect(&map_size, index - page_start);
+        let is_selected = index == screen.selected;
+        let is_unlocked = is_unlocked(main_state, index);
+        let is_completed = main_state
+            .campaign
+            .map_name(index)
+            .map_or(false, |map_name| main_state.progress.is_completed(map_name));
+
+        if is_selected {
+            let frame = graphics::Rect::new(
+                (rect.x + CELL_PADDING * 0.5) * tile,
+                (rect.y + CELL_PADDING * 0.5) * tile,
+                (rect.w - CELL_PADDING) * tile,
+                (rect.h - CELL_PADDING) * tile,
+            );
+            let mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                frame,
+                constantes::COLOR_MENU_SELECTED,
+            )?;
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        }
+
+        // Thumbnail fills the cell above the name, keeping the map proportions
+        let area_w = rect.w - CELL_PADDING * 2.0;
+        let area_h = rect.h - CELL_PADDING * 2.0 - NAME_HEIGHT;
+        if let Some(thumbnail) = &entry.thumbnail {
+            let tile_size = (area_w / thumbnail.map_size.x as f32)
+                .min(area_h / thumbnail.map_size.y as f32);
+            let thumbnail_w = tile_size * thumbnail.map_size.x as f32;
+            let origin = na::Point2::new(
+                rect.x + CELL_PADDING + (area_w - thumbnail_w) * 0.5,
+                rect.y + CELL_PADDING,
+            );
+            let color = match is_unlocked {
+                true => graphics::WHITE,
+                false => constantes::COLOR_THUMBNAIL_LOCKED,
+            };
+            render_thumbnail(
+                thumbnail,
+                &mut main_state.sprite_collection,
+                ctx,
+                origin * tile,
+                tile_size * tile,
+                color,
+            )?;
+        }
+
+        let name_color = if is_selected {
+            constantes::COLOR_MENU_SELECTED
+        } else if !is_unlocked {
+            constantes::COLOR_MENU_LOCKED
+        } else if is_completed {
+            constantes::COLOR_MENU_COMPLETED
+        } else {
+            white
+        };
+        let text = &entry.name_text;
+        let (text_w, text_h) = text.dimensions(ctx);
+        let scale = (NAME_HEIGHT * 0.7 * tile / text_h).min(area_w * tile / text_w);
+        let dest = na::Point2::new(
+            (rect.x + rect.w * 0.5) * tile - text_w * scale * 0.5,
+            (rect.y + rect.h - CELL_PADDING - NAME_HEIGHT) * tile,
+        );
+        let params = graphics::DrawParam::default()
+            .dest(dest)
+            .scale(na::Vector2::new(scale, scale))
+            .color(name_color);
+        graphics::draw(ctx, text, params)?;
+    }
+
+    // Page arrows, tap them or swipe
+    let footer_row = map_size.y as f32 - FOOTER_HEIGHT;
+    let height = FOOTER_HEIGHT * 0.7;
+    if let Some(page_text) = screen.page_texts.get(page) {
+        util::render_text_row(page_text, ctx, &screen_size, &map_size, footer_row, height, white)?;
+    }
+    let arrows = [
+        (&screen.previous_text, 0.5, page > 0),
+        (&screen.next_text, map_size.x as f32 - 0.5, page + 1 < screen.page_texts.len()),
+    ];
+    for (text, center_x, is_enabled) in arrows.iter() {
+        if !is_enabled {
+            continue;
+        }
+        let (text_w, text_h) = text.dimensions(ctx);
+        let scale = height * tile / text_h;
+        let dest = na::Point2::new(
+            center_x * tile - text_w * scale * 0.5,
+            (footer_row + (FOOTER_HEIGHT - height) * 0.5) * tile,
+        );
+        let params = graphics::DrawParam::default()
+            .dest(dest)
+            .scale(na::Vector2::new(scale, scale))
+            .color(white);
+        graphics::draw(ctx, *text, params)?;
+    }
+    Ok(())
+}
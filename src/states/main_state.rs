@@ -1,19 +1,37 @@
+use crate::assets::{self, Assets};
+use crate::campaign::Campaign;
 use crate::constantes;
+use crate::effects::ParticleEffects;
 use crate::entities::skeleton;
-use crate::particle_system::{
-    AngleData, ParticleSystem, ParticleSystemCollection, ValueGetter, VelocityType,
-};
-use crate::sound_collection::SoundCollection;
-use crate::sprite::{self, SpriteCollection};
+use crate::events::{EventBus, GameEvent};
+use crate::game_rng::GameRng;
+use crate::history::History;
+use crate::particle_system::ParticleSystemCollection;
+use crate::progress::{self, Progress};
+use crate::replay::{Playback, Replay};
+use crate::simulation;
+use crate::solver::{self, SolveResult};
+use crate::sound_collection::{Channel, Sound, SoundCollection};
+use crate::speedrun::{self, RunState, Speedrun};
+use crate::sprite::{self, SpriteCollection, Texture};
+use crate::states::credits::{self, CreditsScreen};
+use crate::states::editor::{self, EditorScreen};
 use crate::states::game_state::GameState;
+use crate::states::level_complete::{self, LevelCompleteScreen};
+use crate::states::level_select::{self, LevelSelectScreen};
+use crate::states::pause::{self, PauseScreen};
+use crate::states::screen::Screen;
+use crate::states::title::{self, TitleScreen};
 use crate::util;
 use crate::{
     entities::{cloud, foilage, player},
-    map,
+    grid,
+    map::{self, MapData},
 };
 use event::KeyCode;
+use ggez::error::GameError;
 use ggez::{audio, event, graphics, Context, GameResult};
-use graphics::{DrawParam, FilterMode, draw};
+use graphics::{DrawParam, draw};
 use gwg as ggez;
 use gwg::input::keyboard::KeyMods;
 use nalgebra as na;
@@ -23,294 +41,199 @@ pub struct MainState {
     pub game_state: GameState,
     pub sprite_collection: SpriteCollection,
     pub sound_collection: SoundCollection,
+    pub campaign: Campaign,
+    pub progress: Progress,
+    pub speedrun: Option<Speedrun>, // None unless a speedrun was started from the title screen
+    pub is_testing_map: bool,       // playing the map from the editor instead of the campaign
+    pub hint: Option<PlayerInputIntent>, // next move of a winning line, shown until the player moves
+    pub hint_text: Option<graphics::Text>, // when there is no hint to show
+    pub hints_used: u32,                // on the current level, restarts included
     pub current_map: usize,
     pub screen_size: na::Point2<f32>,
     pub black_border_left: Option<util::BlackBorder>,
     pub black_border_right: Option<util::BlackBorder>,
     pub particle_systems: ParticleSystemCollection,
     // Particle system ids
-    pub grass_id: u32,
-    pub step_id: u32,
-    pub blood_id: u32,
-    pub land_id: u32,
-    pub foilage_1_id: u32,
-    pub foilage_2_id: u32,
-    pub foilage_3_id: u32,
-    pub foilage_4_id: u32,
+    pub effects: ParticleEffects,
     pub mouse_pos_down: na::Vector2<f32>,
+    pub input_intent: PlayerInputIntent,
+    pub time_since_step: f32,
+    pub landing: Option<GameEvent>, // the Landed event waits for the fall to visually finish
+    pub history: History,
+    pub level_seed: u64,
+    pub rng: GameRng, // reseeded with the level seed, so replays look the same
+    pub playback: Option<Playback>,
+    pub game_over_text: graphics::Text,
+    pub moves_text: graphics::Text,
+    pub font: graphics::Font,
+    pub map_error_text: Option<graphics::Text>, // the current map couldn't be loaded
+    pub screens: Vec<Screen>,                     // top screen gets the input, Title is always at the bottom
+    pub title: TitleScreen,
+    pub level_select: LevelSelectScreen,
+    pub pause: PauseScreen,
+    pub level_complete: LevelCompleteScreen,
+    pub credits: CreditsScreen,
+    pub editor: EditorScreen,
 }
 
 impl MainState {
     pub fn new(ctx: &mut Context) -> GameResult<MainState> {
-        let mut images = [
-            graphics::Image::new(ctx, "textures/player.png")?,
-            graphics::Image::new(ctx, "textures/ground.png")?,
-            graphics::Image::new(ctx, "textures/unburied.png")?,
-            graphics::Image::new(ctx, "textures/buried.png")?,
-            graphics::Image::new(ctx, "textures/skeleton_neutral.png")?,
-            graphics::Image::new(ctx, "textures/blue_door.png")?,
-            graphics::Image::new(ctx, "textures/red_door.png")?,
-            graphics::Image::new(ctx, "textures/skeleton_attack.png")?,
-            graphics::Image::new(ctx, "textures/player_dig.png")?,
-            graphics::Image::new(ctx, "textures/player_dead.png")?,
-            graphics::Image::new(ctx, "textures/ground_below.png")?,
-            graphics::Image::new(ctx, "textures/sound_on.png")?,
-            graphics::Image::new(ctx, "textures/sound_off.png")?,
-            graphics::Image::new(ctx, "textures/player_fall.png")?,
-            graphics::Image::new(ctx, "textures/foilage_1.png")?,
-            graphics::Image::new(ctx, "textures/foilage_2.png")?,
-            graphics::Image::new(ctx, "textures/foilage_3.png")?,
-            graphics::Image::new(ctx, "textures/foilage_4.png")?,
-            graphics::Image::new(ctx, "textures/cloud_1.png")?,
-            graphics::Image::new(ctx, "textures/cloud_2.png")?,
-            graphics::Image::new(ctx, "textures/cloud_3.png")?,
-        ];
-
-        for img in &mut images {
-            img.set_filter(FilterMode::Nearest);
-        }
-
-        let sprite_collection = SpriteCollection { images };
-
-        let sounds = [
-            audio::Source::new(ctx, "sounds/player_walk.wav")?,
-            audio::Source::new(ctx, "sounds/player_dig.wav")?,
-            audio::Source::new(ctx, "sounds/player_hit.wav")?,
-            audio::Source::new(ctx, "sounds/player_teleport.wav")?,
-            audio::Source::new(ctx, "sounds/level_completed.wav")?,
-            audio::Source::new(ctx, "sounds/skeleton_attack.wav")?,
-            audio::Source::new(ctx, "sounds/door_locked.wav")?,
-            audio::Source::new(ctx, "sounds/player_fall.wav")?,
-            audio::Source::new(ctx, "sounds/player_land.wav")?,
-            audio::Source::new(ctx, "sounds/level_restarted.wav")?,
-        ];
-        let sound_collection = SoundCollection {
-            is_on: true,
-            sounds,
-        };
+        let assets = Assets::load(ctx).map_err(|e| GameError::ResourceLoadError(e.to_string()))?;
+        let sprite_collection = SpriteCollection::load(assets.atlas_image, &assets.atlas_data)?;
+        let sound_collection = SoundCollection::new(assets.sounds, assets.music);
+        let font = assets.font;
+
         let mut particle_systems = ParticleSystemCollection::new();
-        let mut grass_particle_system = ParticleSystem::new(ctx, None);
-        grass_particle_system.start_color = ValueGetter::Range(
-            (82.0 / 255.0, 166.0 / 255.0, 32.0 / 255.0).into(),
-            (66.0 / 255.0, 54.0 / 255.0, 39.0 / 255.0).into(),
+        let effects = ParticleEffects::new(
+            ctx,
+            &assets.particle_presets,
+            &sprite_collection,
+            &mut particle_systems,
         );
 
-        grass_particle_system.velocity_type =
-            VelocityType::Angle(AngleData::new(constantes::PI, Some(0.4)));
-        grass_particle_system.start_speed = ValueGetter::Range(2.0, 3.0);
-        grass_particle_system.start_lifetime = ValueGetter::Range(0.4, 0.5);
-        grass_particle_system.start_scale = ValueGetter::Range(2.0, 3.4);
-        grass_particle_system.start_angular_velocity = ValueGetter::Range(2.0, 30.4);
-
-        let mut step_particle_system = ParticleSystem::new(ctx, None);
-        step_particle_system.start_lifetime = ValueGetter::Range(0.2, 0.3);
-        step_particle_system.start_scale = ValueGetter::Range(1.0, 2.4);
-        step_particle_system.start_color =
-            ValueGetter::Single((82.0 / 255.0, 166.0 / 255.0, 32.0 / 255.0).into());
-
-        let mut blood_particle_system = ParticleSystem::new(ctx, None);
-        blood_particle_system.start_lifetime = ValueGetter::Range(0.3, 0.5);
-        blood_particle_system.start_scale = ValueGetter::Range(1.0, 10.4);
-        blood_particle_system.start_color = ValueGetter::Single(constantes::COLOR_BLOOD);
-
-        let mut land_particle_system = ParticleSystem::new(ctx, None);
-        land_particle_system.start_lifetime = ValueGetter::Range(0.3, 0.5);
-        land_particle_system.start_speed = ValueGetter::Range(0.3, 1.5);
-        land_particle_system.start_scale = ValueGetter::Range(1.0, 4.4);
-        land_particle_system.gravity = -1.0;
-        land_particle_system.start_color = ValueGetter::Single(ggez::graphics::WHITE);
-
-        // Foilage particles
-        let foilage_1_image = sprite_collection
-            .images
-            .get(14)
-            .expect("no foilage 1 image");
-        let foilage_2_image = sprite_collection
-            .images
-            .get(15)
-            .expect("no foilage 2 image");
-        let foilage_3_image = sprite_collection
-            .images
-            .get(16)
-            .expect("no foilage 3 image");
-        let foilage_4_image = sprite_collection
-            .images
-            .get(17)
-            .expect("no foilage 4 image");
-
-        let mut foilage_1_particle_system = ParticleSystem::new(ctx, Some(foilage_1_image.clone()));
-        foilage_1_particle_system.start_color = ValueGetter::Single(graphics::WHITE);
-        foilage_1_particle_system.velocity_type =
-            VelocityType::Angle(AngleData::new(constantes::PI, Some(0.1)));
-        foilage_1_particle_system.start_speed = ValueGetter::Range(2.0, 7.0);
-        foilage_1_particle_system.end_scale = 3.0;
-        foilage_1_particle_system.start_lifetime = ValueGetter::Single(6.0);
-        foilage_1_particle_system.start_scale = ValueGetter::Single(1.0);
-
-        let mut foilage_2_particle_system = ParticleSystem::new(ctx, Some(foilage_2_image.clone()));
-        let mut foilage_3_particle_system = ParticleSystem::new(ctx, Some(foilage_3_image.clone()));
-        let mut foilage_4_particle_system = ParticleSystem::new(ctx, Some(foilage_4_image.clone()));
-        foilage_2_particle_system.copy_settings(&foilage_1_particle_system);
-        foilage_3_particle_system.copy_settings(&foilage_1_particle_system);
-        foilage_4_particle_system.copy_settings(&foilage_1_particle_system);
-
-        let grass_id = particle_systems.add_system(grass_particle_system);
-        let step_id = particle_systems.add_system(step_particle_system);
-        let blood_id = particle_systems.add_system(blood_particle_system);
-        let land_id = particle_systems.add_system(land_particle_system);
-        let foilage_1_id = particle_systems.add_system(foilage_1_particle_system);
-        let foilage_2_id = particle_systems.add_system(foilage_2_particle_system);
-        let foilage_3_id = particle_systems.add_system(foilage_3_particle_system);
-        let foilage_4_id = particle_systems.add_system(foilage_4_particle_system);
-
-        let game_state = GameState::new(ctx);
+        let game_over_text = graphics::Text::new(("PRESS (R) to restart!", font, 60.0));
+
+        // Without a campaign there is nothing to play, the error is shown instead
+        let (campaign, campaign_error) = match Campaign::load(ctx) {
+            Ok(campaign) => (campaign, None),
+            Err(e) => (Campaign::empty(), Some(e.to_string())),
+        };
+
+        let level_select = LevelSelectScreen::new(ctx, font, &campaign);
+
+        let game_state = GameState::new();
         let mut main_state = MainState {
             sprite_collection,
             sound_collection,
             game_state,
+            campaign,
+            progress: Progress::load(),
+            speedrun: None,
+            is_testing_map: false,
+            hint: None,
+            hint_text: None,
+            hints_used: 0,
             current_map: 0,
             screen_size: na::Point2::new(0.0, 0.0),
             black_border_left: None,
             black_border_right: None,
             particle_systems,
-            grass_id,
-            step_id,
-            blood_id,
-            land_id,
-            foilage_1_id,
-            foilage_2_id,
-            foilage_3_id,
-            foilage_4_id,
+            effects,
             mouse_pos_down: na::Vector2::new(0.0, 0.0),
+            input_intent: PlayerInputIntent::None,
+            time_since_step: 0.0,
+            landing: None,
+            history: History::new(),
+            level_seed: 0,
+            rng: GameRng::new(0),
+            playback: None,
+            game_over_text,
+            moves_text: graphics::Text::new(("", font, 40.0)),
+            font,
+            map_error_text: None,
+            screens: vec![Screen::Title],
+            title: TitleScreen::new(font),
+            level_select,
+            pause: PauseScreen::new(font),
+            level_complete: LevelCompleteScreen::new(font),
+            credits: CreditsScreen::new(font),
+            editor: EditorScreen::new(font),
         };
 
-        use ggez::event::EventHandler;
-        let (w, h) = ggez::graphics::size(ctx);
-        main_state.resize_event(ctx, w, h);
         audio::maybe_create_soundmixer(ctx);
+        let _ = main_state.sound_collection.start_music();
 
-        map::load_map(ctx, &mut main_state.game_state, 0, &main_state.screen_size);
+        // The title screen shows the level the player would continue from
+        let start_level = main_state.continue_level();
+        let seed = new_seed(ctx);
+        main_state.load_level(ctx, start_level, seed);
+        if let Some(message) = campaign_error {
+            eprintln!("{}", message);
+            main_state.map_error_text = Some(graphics::Text::new((message, font, 30.0)));
+        }
         Ok(main_state)
     }
-    pub fn restart_current_map(&mut self, ctx: &mut Context) {
-        if self.game_state.is_all_levels_completed {
-            self.current_map = 0;
-            self.game_state.is_all_levels_completed = false;
-        }
+
+    // Every attempt starts here, the seed decides the decoration (clouds, foilage) and particles
+    pub fn load_level(&mut self, ctx: &mut Context, map_index: usize, seed: u64) {
         map::clear_map(&mut self.game_state);
-        map::load_map(
+        self.current_map = map_index;
+        self.level_seed = seed;
+        self.rng.reseed(seed);
+        self.map_error_text = None;
+        let map_name = self.campaign.map_name(map_index).unwrap_or("").to_string();
+        if let Err(error) = map::load_map(
             ctx,
             &mut self.game_state,
-            self.current_map,
+            &map_name,
             &self.screen_size,
-        );
-        self.sound_collection.play(9);
-    }
-}
-
-impl event::EventHandler for MainState {
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        let delta = ggez::timer::delta(ctx).as_secs_f32();
-        if self.game_state.is_all_levels_completed {
-            return Ok(());
+            &mut self.rng,
+        ) {
+            // Show what's wrong with the map instead of playing half of it
+            let message = format!("{}: {}", map_name, error);
+            eprintln!("{}", message);
+            map::clear_map(&mut self.game_state);
+            self.map_error_text = Some(graphics::Text::new((message, self.font, 30.0)));
         }
-        self.particle_systems.update(delta);
-
-        cloud::update(&mut self.game_state, ctx);
-
-        let player = &mut self.game_state.player;
-        let should_step = player.should_step(
-            delta,
-            &self.game_state.grasses,
-            &self.game_state.skeletons,
-            &self.game_state.skeleton_blocks,
-            &mut self.sound_collection,
-            &mut self.particle_systems,
-            &self.land_id,
-            &self.screen_size,
-        );
-
-        if should_step {
-            player::system(
-                &mut self.game_state,
-                ctx,
-                &mut self.current_map,
-                &mut self.sound_collection,
-                &self.screen_size,
-                &mut self.particle_systems,
-                &self.grass_id,
-                &self.step_id,
-                &self.foilage_1_id,
-                &self.foilage_2_id,
-                &self.foilage_3_id,
-                &self.foilage_4_id,
-            );
-
-            skeleton::system(
-                &mut self.game_state,
-                ctx,
-                &mut self.sound_collection,
-                &mut self.particle_systems,
-                &self.blood_id,
-                &self.screen_size,
-            );
+        self.start_attempt(ctx);
+    }
 
-            skeleton::block_system(&mut self.game_state, &mut self.sound_collection);
+    // Plays a map that isn't part of the campaign, completing it goes back to the editor
+    pub fn test_map(&mut self, ctx: &mut Context, map_data: &MapData) {
+        map::clear_map(&mut self.game_state);
+        if !self.is_testing_map {
+            self.hints_used = 0;
         }
-        Ok(())
+        self.is_testing_map = true;
+        self.playback = None;
+        self.speedrun = None;
+        self.level_seed = new_seed(ctx);
+        self.rng.reseed(self.level_seed);
+        self.map_error_text = None;
+        map::start_map(&mut self.game_state, map_data, &self.screen_size, &mut self.rng);
+        self.start_attempt(ctx);
+        self.screens = vec![Screen::Title, Screen::Editor, Screen::Playing];
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        graphics::clear(ctx, constantes::CLEAR_COLOR);
-        render_system(
-            &mut self.game_state,
-            &self.sprite_collection,
-            ctx,
-            &self.screen_size,
-            &self.sound_collection,
-            &self.black_border_left,
-            &self.black_border_right,
-        );
-        self.particle_systems.draw(ctx).unwrap();
-        graphics::present(ctx)?;
-        Ok(())
+    fn start_attempt(&mut self, ctx: &mut Context) {
+        let (w, h) = graphics::size(ctx);
+        self.fit_to_window(ctx, w, h);
+        self.input_intent = PlayerInputIntent::None;
+        self.landing = None;
+        self.time_since_step = 0.0;
+        self.history.clear();
+        self.clear_hint();
+        self.update_moves_text();
     }
 
-    fn key_down_event(
-        &mut self,
-        ctx: &mut Context,
-        keycode: KeyCode,
-        _keymod: KeyMods,
-        repeat: bool,
-    ) {
-        if repeat {
-            return;
-        }
+    pub fn open_editor(&mut self, ctx: &mut Context) {
+        self.fit_editor(ctx);
+        self.push_screen(Screen::Editor);
+    }
 
-        let intent = match keycode {
-            KeyCode::Right | KeyCode::D => PlayerInputIntent::Right,
-            KeyCode::Left | KeyCode::A => PlayerInputIntent::Left,
-            KeyCode::Down | KeyCode::S => PlayerInputIntent::Down,
-            KeyCode::Up | KeyCode::W => PlayerInputIntent::Up,
-            _ => PlayerInputIntent::None,
-        };
+    // The editor is drawn at the size of the map being edited
+    pub fn fit_editor(&mut self, ctx: &mut Context) {
+        map::clear_map(&mut self.game_state);
+        self.game_state.map_size = self.editor.size;
+        grid::rebuild(&mut self.game_state);
+        cloud::spawn(&mut self.game_state, &mut self.rng);
+        let (w, h) = graphics::size(ctx);
+        self.fit_to_window(ctx, w, h);
+    }
 
-        self.game_state.player.input_intent = intent;
-        match keycode {
-            KeyCode::R => {
-                self.restart_current_map(ctx);
-            }
-            KeyCode::M => {
-                self.sound_collection.is_on = !self.sound_collection.is_on;
-            }
-            _ => {}
-        }
+    // Back from test playing, the map in the editor is unchanged
+    pub fn return_to_editor(&mut self) {
+        self.is_testing_map = false;
+        self.playback = None;
+        self.screens.truncate(1);
+        self.screens.push(Screen::Editor);
     }
 
-    fn resize_event(&mut self, ctx: &mut Context, w: f32, h: f32) {
+    // Scales the map to fill the window, every map can have a different size
+    fn fit_to_window(&mut self, ctx: &mut Context, w: f32, h: f32) {
         // This scaling code is a mess, send halp
-        let map_w = 10.0;
-        let map_h = 8.0;
+        let map_w = self.game_state.map_size.x as f32;
+        let map_h = self.game_state.map_size.y as f32;
         let sprite_scale = (h / map_h).min(w / map_w);
         self.screen_size.x = sprite_scale;
         self.screen_size.y = sprite_scale;
@@ -339,6 +262,628 @@ impl event::EventHandler for MainState {
         );
     }
 
+    pub fn current_screen(&self) -> Screen {
+        *self.screens.last().unwrap_or(&Screen::Title)
+    }
+
+    pub fn push_screen(&mut self, screen: Screen) {
+        match screen {
+            Screen::Title => self.title.menu.selected = 0,
+            Screen::Paused => self.pause.menu.selected = 0,
+            Screen::LevelSelect => self.level_select.selected = self.current_map,
+            _ => {}
+        }
+        self.screens.push(screen);
+    }
+
+    // The title screen is never popped
+    pub fn pop_screen(&mut self) {
+        if self.screens.len() > 1 {
+            self.screens.pop();
+        }
+    }
+
+    pub fn return_to_title(&mut self) {
+        self.save_attempt();
+        self.is_testing_map = false;
+        self.playback = None;
+        // Leaving to the title abandons a speedrun, a finished one has already been saved
+        self.speedrun = None;
+        self.screens.truncate(1);
+        self.title.menu.selected = 0;
+    }
+
+    // Furthest level reached, where Play on the title screen continues from
+    pub fn continue_level(&self) -> usize {
+        let last_level = self.campaign.len().saturating_sub(1);
+        self.progress.highest_level(&self.campaign).min(last_level)
+    }
+
+    pub fn continue_campaign(&mut self, ctx: &mut Context) {
+        let level = self.continue_level();
+        self.play_level(ctx, level);
+    }
+
+    pub fn play_level(&mut self, ctx: &mut Context, map_index: usize) {
+        // Going anywhere but the next level of the campaign abandons a speedrun
+        let is_next_split = self.speedrun.as_ref().map_or(false, |run| {
+            run.state != RunState::Finished && run.next_level() == map_index
+        });
+        if !is_next_split {
+            self.speedrun = None;
+        }
+        self.playback = None;
+        self.is_testing_map = false;
+        self.hints_used = 0;
+        let seed = new_seed(ctx);
+        self.load_level(ctx, map_index, seed);
+        self.screens = vec![Screen::Title, Screen::Playing];
+    }
+
+    // Times the whole campaign from the first level, with a split for every level
+    pub fn start_speedrun(&mut self, ctx: &mut Context) {
+        self.speedrun = Some(Speedrun::new(&self.campaign));
+        self.play_level(ctx, 0);
+    }
+
+    // After the level complete screen, the last level leads to the credits
+    pub fn next_level(&mut self, ctx: &mut Context) {
+        let next_map = self.current_map + 1;
+        if next_map >= self.campaign.len() {
+            self.screens = vec![Screen::Title, Screen::Credits];
+        } else {
+            self.play_level(ctx, next_map);
+        }
+    }
+
+    // F5, picks up changes to the particle presets in resources/particles without restarting
+    fn reload_particle_presets(&mut self) {
+        match assets::reload_particle_presets() {
+            Ok(presets) => {
+                self.effects.reload(&presets, &mut self.particle_systems);
+                eprintln!("reloaded {} particle presets", presets.len());
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    pub fn restart_current_map(&mut self, ctx: &mut Context) {
+        self.sound_collection.play(Sound::LevelRestarted);
+        if self.is_testing_map {
+            if let Ok(map_data) = self.editor.parse() {
+                self.test_map(ctx, &map_data);
+            }
+            return;
+        }
+        self.save_attempt();
+        self.playback = None;
+        let seed = new_seed(ctx);
+        self.load_level(ctx, self.current_map, seed);
+    }
+
+    // The current attempt, as far as it got
+    pub fn current_replay(&self) -> Replay {
+        let map_name = self.campaign.map_name(self.current_map).unwrap_or("");
+        let mut replay = Replay::new(map_name, self.level_seed);
+        replay.inputs = self.history.moves();
+        replay
+    }
+
+    // Every attempt gets its own file, named after the map and the attempt's seed, which is new
+    // for every attempt. Saving again during the same attempt updates its file.
+    // Watching a replay doesn't record a replay of it
+    pub fn save_attempt(&self) {
+        if self.history.moves().is_empty() || self.is_testing_map || self.playback.is_some() {
+            return;
+        }
+        let map_name = self.campaign.map_name(self.current_map).unwrap_or("");
+        let map_stem = std::path::Path::new(map_name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("map");
+        let path = format!(
+            "{}/{}_{}.txt",
+            constantes::REPLAY_DIRECTORY,
+            map_stem,
+            self.level_seed
+        );
+        // Not every platform has a file system (wasm), replays are a nice to have
+        if self.current_replay().save(&path).is_ok() {
+            eprintln!("saved replay {}", path);
+        }
+    }
+
+    // Replays the attempt from the start, instead of listening to keyboard/touch input
+    pub fn start_playback(&mut self, ctx: &mut Context, replay: Replay) {
+        self.screens = vec![Screen::Title, Screen::Playing];
+        self.speedrun = None;
+        self.hints_used = 0;
+        match self.campaign.find_map_index(&replay.map_name) {
+            Some(map_index) => {
+                self.load_level(ctx, map_index, replay.seed);
+                self.playback = Some(Playback::new(replay));
+            }
+            None => {
+                // Shown like a broken map, instead of silently staying on the title screen
+                let message = format!(
+                    "{}: not in the campaign, can't play the replay",
+                    replay.map_name
+                );
+                eprintln!("{}", message);
+                map::clear_map(&mut self.game_state);
+                self.playback = None;
+                self.map_error_text = Some(graphics::Text::new((message, self.font, 30.0)));
+            }
+        }
+    }
+
+    // Can't rewind while a fall is still being resolved
+    fn can_rewind(&self) -> bool {
+        !self.game_state.player.is_alive || simulation::is_player_grounded(&self.game_state)
+    }
+
+    pub fn undo(&mut self) {
+        if self.can_rewind() && self.history.undo(&mut self.game_state) {
+            self.after_rewind();
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.can_rewind() && self.history.redo(&mut self.game_state) {
+            self.after_rewind();
+        }
+    }
+
+    fn after_rewind(&mut self) {
+        self.input_intent = PlayerInputIntent::None;
+        self.landing = None;
+        self.time_since_step = 0.0;
+        skeleton::update_sprites(&mut self.game_state);
+        util::force_actor_visual_positions(&mut self.game_state, &self.screen_size);
+        self.clear_hint();
+        self.update_moves_text();
+    }
+
+    // Searches for a win from where the player stands, so the hint is right
+    // even after leaving the intended path
+    pub fn show_hint(&mut self) {
+        let is_stuck = !self.game_state.player.is_alive || self.map_error_text.is_some();
+        if is_stuck || self.hint.is_some() || !self.can_rewind() || self.playback.is_some() {
+            return;
+        }
+        let message = match solver::solve(&self.game_state, solver::HINT_MAX_STATES) {
+            // Only a hint actually shown counts, an empty solution has nothing to show
+            SolveResult::Solved(moves) => match moves.first() {
+                Some(&first_move) => {
+                    self.hint = Some(first_move);
+                    self.hints_used += 1;
+                    self.update_moves_text();
+                    return;
+                }
+                None => "No hint found",
+            },
+            SolveResult::Unsolvable => "No way out from here, undo or restart",
+            SolveResult::GaveUp => "No hint found",
+        };
+        self.hint_text = Some(graphics::Text::new((message, self.font, 40.0)));
+    }
+
+    fn clear_hint(&mut self) {
+        self.hint = None;
+        self.hint_text = None;
+    }
+
+    // Move counter shown while playing, rebuilt after every turn instead of every frame
+    fn update_moves_text(&mut self) {
+        let moves = self.game_state.move_count;
+        let par = match self.is_testing_map {
+            true => None,
+            false => self.campaign.par(self.current_map),
+        };
+        let mut line = match par {
+            Some(par) => format!("Moves {} / Par {}", moves, par),
+            None => format!("Moves {}", moves),
+        };
+        if self.hints_used > 0 {
+            line.push_str(&format!("   Hints {}", self.hints_used));
+        }
+        self.moves_text = graphics::Text::new((line, self.font, 40.0));
+    }
+
+    // Runs one turn of the rules, then shows what happened
+    pub fn step(&mut self, intent: PlayerInputIntent) {
+        if let Some(run) = &mut self.speedrun {
+            if intent != PlayerInputIntent::None && run.next_level() == self.current_map {
+                run.start();
+            }
+        }
+        if intent != PlayerInputIntent::None {
+            self.clear_hint();
+        }
+        let events = simulation::simulate_turn(&mut self.game_state, intent);
+        self.dispatch(&events);
+        self.update_moves_text();
+    }
+
+    // Hands the events of a turn to every listener
+    fn dispatch(&mut self, events: &EventBus) {
+        for event in events.events() {
+            if let GameEvent::Landed { .. } = event {
+                // Delivered by land() once the fall has visually finished
+                self.landing = Some(event.clone());
+                continue;
+            }
+            self.notify(event);
+        }
+        skeleton::update_sprites(&mut self.game_state);
+    }
+
+    fn notify(&mut self, event: &GameEvent) {
+        self.sound_collection.on_event(event);
+        self.effects.on_event(
+            event,
+            &mut self.particle_systems,
+            &self.game_state,
+            &self.screen_size,
+            &mut self.rng,
+        );
+        self.on_event(event);
+    }
+
+    // UI listener, sprites react to what happened and the level complete summary shows up
+    fn on_event(&mut self, event: &GameEvent) {
+        let screen_size = self.screen_size;
+        let game_state = &mut self.game_state;
+        let player = &mut game_state.player;
+        match event {
+            GameEvent::PlayerStartedFalling => player.sprite.texture = Texture::PlayerFall,
+            GameEvent::PlayerWarped => {
+                // Force visual insta jump
+                let pos_player_unscaled =
+                    na::convert::<na::Point2<i32>, na::Point2<f32>>(player.transform.position);
+                player.sprite.visual_position = pos_player_unscaled * screen_size.x;
+                player.sprite.blink_timer = constantes::TIME_BLINK;
+            }
+            GameEvent::PlayerActed(intent) => {
+                player.sprite.texture = Texture::Player;
+                match intent {
+                    PlayerInputIntent::Left => player.sprite.is_flipped = true,
+                    PlayerInputIntent::Right => player.sprite.is_flipped = false,
+                    _ => {}
+                }
+            }
+            GameEvent::Landed { .. } => player.sprite.texture = Texture::Player,
+            GameEvent::Teleported { teleporter } => {
+                player.sprite.blink_timer = constantes::TIME_BLINK;
+                if let Some(other_teleporter) = &mut game_state.teleporters[*teleporter] {
+                    other_teleporter.sprite.blink_timer = constantes::TIME_BLINK;
+                }
+            }
+            GameEvent::ExitLocked => {
+                for skeleton_block in game_state.skeleton_blocks.iter_mut() {
+                    skeleton_block.sprite.blink_timer = constantes::TIME_BLINK;
+                }
+            }
+            GameEvent::PlayerDug { .. } => player.sprite.texture = Texture::PlayerDig,
+            GameEvent::PlayerKilled { .. } => player.sprite.texture = Texture::PlayerDead,
+            GameEvent::SkeletonTurned {
+                skeleton,
+                is_flipped,
+            } => {
+                if let Some(skeleton) = game_state.skeletons.get_mut(*skeleton) {
+                    skeleton.sprite.is_flipped = *is_flipped;
+                }
+            }
+            GameEvent::SkeletonWarped { skeleton } => {
+                if let Some(skeleton) = game_state.skeletons.get_mut(*skeleton) {
+                    skeleton.sprite.blink_timer = constantes::TIME_BLINK;
+                    let position =
+                        na::convert::<na::Point2<i32>, na::Point2<f32>>(skeleton.transform.position);
+                    skeleton.sprite.visual_position = position * screen_size.x;
+                }
+            }
+            GameEvent::LevelCompleted => self.complete_level(),
+            GameEvent::PlayerStepped { .. }
+            | GameEvent::SkeletonReleased { .. }
+            | GameEvent::SkeletonAttacking { .. } => {}
+        }
+    }
+
+    fn complete_level(&mut self) {
+        if self.is_testing_map {
+            let message = format!("Escaped in {} moves", self.game_state.move_count);
+            self.editor.set_status(&message);
+            self.return_to_editor();
+            return;
+        }
+        if self.playback.is_some() {
+            // A replay finishing isn't a real completion, progress and best moves stay as they are
+            self.return_to_title();
+            return;
+        }
+        if let Some(run) = &mut self.speedrun {
+            run.split(&self.campaign, self.current_map);
+        }
+        self.save_attempt();
+        let map_name = self.campaign.map_name(self.current_map).unwrap_or("");
+        let moves = self.game_state.move_count;
+        let par = self.campaign.par(self.current_map);
+        let stars = par.map_or(0, |par| progress::star_rating(moves, par));
+        let is_new_best = self
+            .progress
+            .complete_level(map_name, moves, stars, self.hints_used);
+        self.progress.save();
+        self.playback = None;
+
+        let title = self.campaign.title(self.current_map).unwrap_or("");
+        let best_moves = self
+            .progress
+            .level(map_name)
+            .and_then(|level| level.best_moves)
+            .unwrap_or(moves);
+        self.level_complete
+            .set_result(title, moves, par, best_moves, is_new_best, self.hints_used);
+        self.push_screen(Screen::LevelComplete);
+    }
+
+    fn land(&mut self) {
+        if let Some(event) = self.landing.take() {
+            self.notify(&event);
+        }
+    }
+}
+
+impl MainState {
+    fn update_playing(&mut self, ctx: &mut Context, delta: f32) {
+        if self.map_error_text.is_some() {
+            return;
+        }
+        self.particle_systems.update(delta);
+
+        cloud::update(&mut self.game_state, ctx);
+
+        if !self.game_state.player.is_alive {
+            return;
+        }
+
+        self.time_since_step += delta;
+        let is_grounded = simulation::is_player_grounded(&self.game_state);
+        if let Some(playback) = &mut self.playback {
+            if is_grounded && self.time_since_step > constantes::TIME_REPLAY_STEP {
+                if let Some(intent) = playback.next_intent(self.game_state.turn) {
+                    self.input_intent = intent;
+                }
+            }
+            // Hand control back to the player when the replay runs out
+            if playback.is_finished() && self.input_intent == PlayerInputIntent::None {
+                self.playback = None;
+            }
+        }
+        if self.input_intent != PlayerInputIntent::None && is_grounded {
+            self.time_since_step = 0.0;
+            let intent = self.input_intent;
+            self.input_intent = PlayerInputIntent::None;
+            self.history.record(&self.game_state, intent);
+            self.step(intent);
+        } else if self.time_since_step > constantes::TIME_AUTO_STEP {
+            if !is_grounded {
+                // Falling, input waits until the player stands on something
+                self.time_since_step = 0.0;
+                self.step(PlayerInputIntent::None);
+            } else if self.landing.is_some() {
+                self.land();
+            }
+        }
+    }
+
+    fn key_down_playing(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        let intent = match keycode {
+            KeyCode::Right | KeyCode::D => PlayerInputIntent::Right,
+            KeyCode::Left | KeyCode::A => PlayerInputIntent::Left,
+            KeyCode::Down | KeyCode::S => PlayerInputIntent::Down,
+            KeyCode::Up | KeyCode::W => PlayerInputIntent::Up,
+            _ => PlayerInputIntent::None,
+        };
+
+        if self.playback.is_none() {
+            self.input_intent = intent;
+        }
+        match keycode {
+            KeyCode::R => {
+                self.restart_current_map(ctx);
+            }
+            KeyCode::Escape if self.is_testing_map => {
+                self.return_to_editor();
+            }
+            KeyCode::Escape | KeyCode::P => {
+                self.input_intent = PlayerInputIntent::None;
+                self.push_screen(Screen::Paused);
+            }
+            KeyCode::Z | KeyCode::U => {
+                self.undo();
+            }
+            KeyCode::Y => {
+                self.redo();
+            }
+            KeyCode::H => {
+                self.show_hint();
+            }
+            KeyCode::F2 => {
+                self.save_attempt();
+            }
+            KeyCode::F3 if !self.is_testing_map => {
+                // watch the current attempt again from the start
+                let replay = self.current_replay();
+                self.start_playback(ctx, replay);
+            }
+            _ => {}
+        }
+    }
+
+    fn mouse_up_playing(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        let current_pos = na::Point2::new(x, y);
+        let pause_rect = pause::button_rect(ctx, &self.screen_size, &self.game_state.map_size);
+        if pause_rect.contains(current_pos) {
+            self.input_intent = PlayerInputIntent::None;
+            self.push_screen(Screen::Paused);
+            return;
+        }
+
+        let current_pos: na::Vector2<f32> = na::Vector2::new(current_pos.x, current_pos.y);
+        let delta = current_pos - self.mouse_pos_down;
+
+        // Undo/redo input, tap the left or right edge of the screen
+        let (w, _h) = ggez::graphics::size(ctx);
+        let is_tap = delta.norm() < constantes::TOUCH_MIN_DELTA;
+        let undo_zone_w = w * constantes::TOUCH_UNDO_ZONE;
+        if is_tap && current_pos.x < undo_zone_w {
+            self.undo();
+            return;
+        } else if is_tap && current_pos.x > w - undo_zone_w {
+            self.redo();
+            return;
+        }
+
+        // Restart input Currently tap anywhere on screen if delta is below move action
+        let is_stuck = !self.game_state.player.is_alive || self.map_error_text.is_some();
+        if is_stuck && is_tap {
+            self.restart_current_map(ctx);
+        }
+
+        // touch input
+        if self.playback.is_none() {
+            self.input_intent = util::swipe_direction(delta);
+        }
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let delta = ggez::timer::delta(ctx).as_secs_f32();
+        // The run clock only runs while a level is played or completed, the pause screen and menus stop it
+        let screen = self.current_screen();
+        let is_timed = screen == Screen::Playing || screen == Screen::LevelComplete;
+        if let Some(run) = &mut self.speedrun {
+            run.update(delta, is_timed);
+        }
+        match screen {
+            Screen::Playing => self.update_playing(ctx, delta),
+            Screen::Paused => {}
+            Screen::LevelComplete => {
+                self.particle_systems.update(delta);
+                cloud::update(&mut self.game_state, ctx);
+            }
+            Screen::Title | Screen::LevelSelect | Screen::Credits | Screen::Editor => {
+                cloud::update(&mut self.game_state, ctx);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::clear(ctx, constantes::CLEAR_COLOR);
+        render_background(ctx, &self.screen_size, &self.game_state.map_size);
+        let screen = self.current_screen();
+        match screen {
+            Screen::Playing | Screen::Paused | Screen::LevelComplete => {
+                render_level(
+                    &mut self.game_state,
+                    &mut self.sprite_collection,
+                    ctx,
+                    &self.screen_size,
+                    &self.sound_collection,
+                    &self.game_over_text,
+                    &self.map_error_text,
+                );
+                self.particle_systems.draw(ctx)?;
+            }
+            Screen::Title | Screen::LevelSelect | Screen::Credits | Screen::Editor => {
+                cloud::render(
+                    &mut self.game_state,
+                    &mut self.sprite_collection,
+                    ctx,
+                    &self.screen_size,
+                )?;
+                self.sprite_collection.draw(ctx)?;
+            }
+        }
+        match screen {
+            Screen::Title => title::draw(self, ctx)?,
+            Screen::LevelSelect => level_select::draw(self, ctx)?,
+            Screen::Playing => {
+                util::render_text_row(
+                    &self.moves_text,
+                    ctx,
+                    &self.screen_size,
+                    &self.game_state.map_size,
+                    0.1,
+                    0.4,
+                    constantes::COLOR_MENU,
+                )?;
+                if let Some(hint) = self.hint {
+                    // Above the player, pointing where to go
+                    let position = self.game_state.player.sprite.visual_position;
+                    let tile = self.screen_size.x;
+                    let center_y = (position.y - tile * 0.4).max(tile * 0.4);
+                    let center = na::Point2::new(position.x + tile * 0.5, center_y);
+                    util::render_arrow(ctx, center, hint, tile * 0.35, constantes::COLOR_HINT)?;
+                }
+                if let Some(hint_text) = &self.hint_text {
+                    let map_size = &self.game_state.map_size;
+                    let color = constantes::COLOR_HINT;
+                    util::render_text_row(hint_text, ctx, &self.screen_size, map_size, 0.55, 0.35, color)?;
+                }
+                pause::draw_button(self, ctx)?;
+            }
+            Screen::Paused => pause::draw(self, ctx)?,
+            Screen::LevelComplete => level_complete::draw(self, ctx)?,
+            Screen::Credits => credits::draw(self, ctx)?,
+            Screen::Editor => editor::draw(self, ctx)?,
+        }
+        if let Some(run) = &mut self.speedrun {
+            if screen != Screen::Title && screen != Screen::LevelSelect {
+                speedrun::draw(run, ctx, self.font, &self.screen_size)?;
+            }
+        }
+        util::render_border(ctx, &self.black_border_left)?;
+        util::render_border(ctx, &self.black_border_right)?;
+        render_sound_button(ctx, &mut self.sprite_collection, &self.sound_collection)?;
+        graphics::present(ctx)?;
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if repeat {
+            return;
+        }
+        match keycode {
+            KeyCode::M => return self.sound_collection.toggle_mute(),
+            KeyCode::PageUp => return self.sound_collection.volume_up(Channel::Master),
+            KeyCode::PageDown => return self.sound_collection.volume_down(Channel::Master),
+            KeyCode::F5 => return self.reload_particle_presets(),
+            _ => {}
+        }
+        match self.current_screen() {
+            Screen::Title => title::key_down(self, ctx, keycode),
+            Screen::LevelSelect => level_select::key_down(self, ctx, keycode),
+            Screen::Playing => self.key_down_playing(ctx, keycode),
+            Screen::Paused => pause::key_down(self, ctx, keycode),
+            Screen::LevelComplete => level_complete::key_down(self, ctx, keycode),
+            Screen::Credits => credits::key_down(self),
+            Screen::Editor => editor::key_down(self, ctx, keycode),
+        }
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, w: f32, h: f32) {
+        self.fit_to_window(ctx, w, h);
+    }
+
     fn mouse_button_down_event(
         &mut self,
         _ctx: &mut Context,
@@ -361,42 +906,29 @@ impl event::EventHandler for MainState {
         let screen_rect = ggez::graphics::screen_coordinates(ctx);
         let volume_rect = ggez::graphics::Rect::new(-screen_rect.x, -screen_rect.y, 64.0, 64.0);
         if volume_rect.contains(current_pos) {
-            self.sound_collection.is_on = !self.sound_collection.is_on;
-        }
-
-        let current_pos: na::Vector2<f32> = na::Vector2::new(current_pos.x, current_pos.y);
-        let delta = current_pos - self.mouse_pos_down;
-
-        // Restart input Currently tap anywhere on screen if delta is below move action
-        if !self.game_state.player.is_alive && delta.norm() < constantes::TOUCH_MIN_DELTA {
-            self.restart_current_map(ctx);
+            self.sound_collection.toggle_mute();
+            return;
         }
 
-        // touch input
-        let mut input_intent = PlayerInputIntent::None;
-        if delta.norm() > constantes::TOUCH_MIN_DELTA {
-            let x_diff = delta.x.abs();
-            let y_diff = delta.y.abs();
-            if x_diff > y_diff {
-                if delta.x > 0.0 {
-                    input_intent = PlayerInputIntent::Right;
-                } else {
-                    input_intent = PlayerInputIntent::Left;
-                }
-            } else {
-                if delta.y > 0.0 {
-                    input_intent = PlayerInputIntent::Down;
-                } else {
-                    input_intent = PlayerInputIntent::Up;
-                }
-            }
+        match self.current_screen() {
+            Screen::Title => title::mouse_up(self, ctx, x, y),
+            Screen::LevelSelect => level_select::mouse_up(self, ctx, x, y),
+            Screen::Playing => self.mouse_up_playing(ctx, x, y),
+            Screen::Paused => pause::mouse_up(self, ctx, x, y),
+            Screen::LevelComplete => level_complete::mouse_up(self, ctx, x, y),
+            Screen::Credits => credits::mouse_up(self),
+            Screen::Editor => editor::mouse_up(self, ctx, x, y),
         }
-        self.game_state.player.input_intent = input_intent;
     }
 }
+
+pub fn new_seed(ctx: &mut Context) -> u64 {
+    ggez::timer::time_since_start(ctx).as_nanos() as u64
+}
+
 fn render_game(
     game_state: &mut GameState,
-    sprite_collection: &SpriteCollection,
+    sprite_collection: &mut SpriteCollection,
     ctx: &mut Context,
     screen_size: &na::Point2<f32>,
     _sound_collection: &SoundCollection,
@@ -455,21 +987,22 @@ fn render_game(
         screen_size,
     ).unwrap();
     foilage::render(game_state, sprite_collection, ctx, screen_size).unwrap();
+    sprite_collection.draw(ctx).unwrap();
 }
 
-fn render_system(
+fn render_level(
     game_state: &mut GameState,
-    sprite_collection: &SpriteCollection,
+    sprite_collection: &mut SpriteCollection,
     ctx: &mut Context,
     screen_size: &na::Point2<f32>,
     sound_collection: &SoundCollection,
-    left_border: &Option<util::BlackBorder>,
-    right_border: &Option<util::BlackBorder>,
+    game_over_text: &graphics::Text,
+    map_error_text: &Option<graphics::Text>,
 ) {
-    render_background(ctx, screen_size);
-
-    if game_state.is_all_levels_completed {
-        render_all_levels_completed(game_state, ctx, screen_size).unwrap();
+    let map_size = game_state.map_size;
+    if let Some(map_error_text) = map_error_text {
+        let offset = na::Vector2::new(0.0, 0.0);
+        util::render_text(map_error_text, ctx, screen_size, &map_size, offset).unwrap();
     } else {
         render_game(
             game_state,
@@ -478,20 +1011,21 @@ fn render_system(
             screen_size,
             sound_collection,
         );
-        render_game_over(game_state, ctx, screen_size).unwrap();
+        render_game_over(game_state, game_over_text, ctx, screen_size).unwrap();
     }
-    util::render_border(ctx, left_border).unwrap();
-    util::render_border(ctx, right_border).unwrap();
-    render_sound_button(ctx, sprite_collection, sound_collection);
 }
 
-fn render_background(ctx: &mut Context, screen_size: &na::Point2<f32>) {
+fn render_background(
+    ctx: &mut Context,
+    screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
+) {
     let screen_coordinates = ggez::graphics::screen_coordinates(ctx);
     let rect = graphics::Rect::new(
         -screen_coordinates.x * 0.0,
         0.0,
-        screen_size.x * 10.0,
-        screen_size.x * 8.0,
+        screen_size.x * map_size.x as f32,
+        screen_size.x * map_size.y as f32,
     );
     let rect_mesh = graphics::Mesh::new_rectangle(
         ctx,
@@ -505,25 +1039,23 @@ fn render_background(ctx: &mut Context, screen_size: &na::Point2<f32>) {
 
 fn render_sound_button(
     ctx: &mut Context,
-    sprite_collection: &SpriteCollection,
+    sprite_collection: &mut SpriteCollection,
     sound_collection: &SoundCollection,
-) {
+) -> GameResult {
     let params = DrawParam::default()
         //.scale(na::Vector2::<f32>::new(flip_scale * final_scale / 16.0, final_scale / 16.0))
         .dest(na::Point2::new(0.0, 0.0));
-    let image_index = match sound_collection.is_on {
-        true => 11,
-        false => 12,
+    let texture = match sound_collection.is_on() {
+        true => Texture::SoundOn,
+        false => Texture::SoundOff,
     };
-    let image = sprite_collection
-        .images
-        .get(image_index)
-        .expect("No image with id...");
-    draw(ctx, image, params).unwrap();
+    sprite_collection.add(texture, params);
+    sprite_collection.draw(ctx)
 }
 
 fn render_game_over(
     game_state: &mut GameState,
+    game_over_text: &graphics::Text,
     ctx: &mut Context,
     screen_size: &na::Point2<f32>,
 ) -> GameResult {
@@ -534,27 +1066,12 @@ fn render_game_over(
         let offset_y = (time_since_start * speed).sin() * distance;
         let offset_x = (time_since_start * speed).cos() * distance;
         util::render_text(
-            &game_state.game_over_text,
+            game_over_text,
             ctx,
             screen_size,
+            &game_state.map_size,
             na::Vector2::new(offset_x, offset_y),
         )?;
     }
     Ok(())
 }
-
-fn render_all_levels_completed(
-    game_state: &mut GameState,
-    ctx: &mut Context,
-    screen_size: &na::Point2<f32>,
-) -> GameResult {
-    if true {
-        util::render_text(
-            &game_state.all_levels_completed_text,
-            ctx,
-            screen_size,
-            na::Vector2::new(0.0, 0.0),
-        )?;
-    }
-    Ok(())
-}
@@ -0,0 +1,79 @@
+use crate::constantes;
+use crate::util;
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+use nalgebra as na;
+
+const ROW_HEIGHT: f32 = 0.9; // in tiles
+
+pub enum MenuInput<T> {
+    None,
+    Back,
+    Activate(T),
+}
+
+// A column of text options, picked with the arrow keys or by tapping one
+pub struct Menu<T: Copy> {
+    pub items: Vec<(T, graphics::Text)>,
+    pub selected: usize,
+    pub top: f32, // row of the first option, in tiles
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(font: graphics::Font, entries: &[(T, &str)], top: f32) -> Self {
+        let items = entries
+            .iter()
+            .map(|(item, label)| (*item, graphics::Text::new((*label, font, 40.0))))
+            .collect();
+        Menu {
+            items,
+            selected: 0,
+            top,
+        }
+    }
+
+    pub fn key_down(&mut self, keycode: KeyCode) -> MenuInput<T> {
+        match keycode {
+            KeyCode::Up | KeyCode::W => {
+                self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+            }
+            KeyCode::Down | KeyCode::S => {
+                self.selected = (self.selected + 1) % self.items.len();
+            }
+            KeyCode::Enter | KeyCode::Space => {
+                return MenuInput::Activate(self.items[self.selected].0);
+            }
+            KeyCode::Escape => return MenuInput::Back,
+            _ => {}
+        }
+        MenuInput::None
+    }
+
+    // position in tiles
+    pub fn mouse_up(&mut self, position: na::Point2<f32>) -> MenuInput<T> {
+        let row = ((position.y - self.top) / ROW_HEIGHT).floor();
+        if row < 0.0 || row as usize >= self.items.len() {
+            return MenuInput::None;
+        }
+        self.selected = row as usize;
+        MenuInput::Activate(self.items[self.selected].0)
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        screen_size: &na::Point2<f32>,
+        map_size: &na::Point2<i32>,
+    ) -> GameResult {
+        for (index, (_item, text)) in self.items.iter().enumerate() {
+            let color = match index == self.selected {
+                true => constantes::COLOR_MENU_SELECTED,
+                false => constantes::COLOR_MENU,
+            };
+            let row = self.top + index as f32 * ROW_HEIGHT;
+            util::render_text_row(text, ctx, screen_size, map_size, row, ROW_HEIGHT * 0.7, color)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use crate::states::main_state::MainState;
+use crate::states::menu::{Menu, MenuInput};
+use crate::states::screen::Screen;
+use crate::{constantes, util};
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+use nalgebra as na;
+
+#[derive(Clone, Copy)]
+pub enum PauseAction {
+    Resume,
+    Hint,
+    Restart,
+    LevelSelect,
+    Title,
+}
+
+const BUTTON_SIZE: f32 = 64.0; // same as the mute button
+
+pub struct PauseScreen {
+    pub button_text: graphics::Text,
+    pub paused_text: graphics::Text,
+    pub menu: Menu<PauseAction>,
+}
+
+impl PauseScreen {
+    pub fn new(font: graphics::Font) -> Self {
+        let entries = [
+            (PauseAction::Resume, "Resume"),
+            (PauseAction::Hint, "Hint"),
+            (PauseAction::Restart, "Restart"),
+            (PauseAction::LevelSelect, "Levels"),
+            (PauseAction::Title, "Title screen"),
+        ];
+        PauseScreen {
+            button_text: graphics::Text::new(("II", font, 40.0)),
+            paused_text: graphics::Text::new(("PAUSED", font, 60.0)),
+            menu: Menu::new(font, &entries, 3.0),
+        }
+    }
+}
+
+fn activate(main_state: &mut MainState, ctx: &mut Context, action: PauseAction) {
+    match action {
+        PauseAction::Resume => main_state.pop_screen(),
+        PauseAction::Hint => {
+            main_state.pop_screen();
+            main_state.show_hint();
+        }
+        PauseAction::Restart => {
+            main_state.pop_screen();
+            main_state.restart_current_map(ctx);
+        }
+        PauseAction::LevelSelect => {
+            main_state.return_to_title();
+            main_state.push_screen(Screen::LevelSelect);
+        }
+        PauseAction::Title => main_state.return_to_title(),
+    }
+}
+
+pub fn key_down(main_state: &mut MainState, ctx: &mut Context, keycode: KeyCode) {
+    if keycode == KeyCode::P {
+        main_state.pop_screen();
+        return;
+    }
+    match main_state.pause.menu.key_down(keycode) {
+        MenuInput::Activate(action) => activate(main_state, ctx, action),
+        MenuInput::Back => main_state.pop_screen(),
+        MenuInput::None => {}
+    }
+}
+
+pub fn mouse_up(main_state: &mut MainState, ctx: &mut Context, x: f32, y: f32) {
+    let position = util::window_to_tiles(ctx, &main_state.screen_size, x, y);
+    if let MenuInput::Activate(action) = main_state.pause.menu.mouse_up(position) {
+        activate(main_state, ctx, action);
+    }
+}
+
+// Drawn on top of the paused level
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let map_size = main_state.game_state.map_size;
+    let pause = &main_state.pause;
+    util::render_overlay(ctx, &screen_size, &map_size)?;
+    util::render_text_row(
+        &pause.paused_text,
+        ctx,
+        &screen_size,
+        &map_size,
+        1.0,
+        1.2,
+        constantes::COLOR_MENU,
+    )?;
+    pause.menu.draw(ctx, &screen_size, &map_size)
+}
+
+// Pause button in the top right corner of the map, in window pixels like the mouse position
+pub fn button_rect(
+    ctx: &mut Context,
+    screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
+) -> graphics::Rect {
+    let screen_rect = graphics::screen_coordinates(ctx);
+    let map_w = screen_size.x * map_size.x as f32;
+    graphics::Rect::new(
+        map_w - BUTTON_SIZE - screen_rect.x,
+        -screen_rect.y,
+        BUTTON_SIZE,
+        BUTTON_SIZE,
+    )
+}
+
+pub fn draw_button(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let map_w = main_state.screen_size.x * main_state.game_state.map_size.x as f32;
+    let text = &main_state.pause.button_text;
+    let (text_w, text_h) = text.dimensions(ctx);
+    let dest = na::Point2::new(
+        map_w - (BUTTON_SIZE + text_w) * 0.5,
+        (BUTTON_SIZE - text_h) * 0.5,
+    );
+    graphics::draw(ctx, text, graphics::DrawParam::default().dest(dest))
+}
@@ -0,0 +1,18 @@
+// The screens stack on top of each other, only the top one gets input and updates.
+// Paused and LevelComplete are drawn over the level they belong to
+//
+// Title -> LevelSelect
+// Title -> Playing -> Paused
+// Title -> Playing -> LevelComplete
+// Title -> Credits
+// Title -> Editor -> Playing (test playing the edited map)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Screen {
+    Title,
+    LevelSelect,
+    Playing,
+    Paused,
+    LevelComplete,
+    Credits,
+    Editor,
+}
@@ -0,0 +1,76 @@
+use crate::states::main_state::MainState;
+use crate::states::menu::{Menu, MenuInput};
+use crate::states::screen::Screen;
+use crate::{constantes, util};
+use event::KeyCode;
+use ggez::{event, graphics, Context, GameResult};
+use gwg as ggez;
+
+#[derive(Clone, Copy)]
+pub enum TitleAction {
+    Play,
+    LevelSelect,
+    Speedrun,
+    Editor,
+    Credits,
+}
+
+pub struct TitleScreen {
+    pub title_text: graphics::Text,
+    pub menu: Menu<TitleAction>,
+}
+
+impl TitleScreen {
+    pub fn new(font: graphics::Font) -> Self {
+        let entries = [
+            (TitleAction::Play, "Play"),
+            (TitleAction::LevelSelect, "Levels"),
+            (TitleAction::Speedrun, "Speedrun"),
+            (TitleAction::Editor, "Editor"),
+            (TitleAction::Credits, "Credits"),
+        ];
+        TitleScreen {
+            title_text: graphics::Text::new(("DIG ESCAPE", font, 60.0)),
+            menu: Menu::new(font, &entries, 3.4),
+        }
+    }
+}
+
+fn activate(main_state: &mut MainState, ctx: &mut Context, action: TitleAction) {
+    match action {
+        TitleAction::Play => main_state.continue_campaign(ctx),
+        TitleAction::LevelSelect => main_state.push_screen(Screen::LevelSelect),
+        TitleAction::Speedrun => main_state.start_speedrun(ctx),
+        TitleAction::Editor => main_state.open_editor(ctx),
+        TitleAction::Credits => main_state.push_screen(Screen::Credits),
+    }
+}
+
+pub fn key_down(main_state: &mut MainState, ctx: &mut Context, keycode: KeyCode) {
+    if let MenuInput::Activate(action) = main_state.title.menu.key_down(keycode) {
+        activate(main_state, ctx, action);
+    }
+}
+
+pub fn mouse_up(main_state: &mut MainState, ctx: &mut Context, x: f32, y: f32) {
+    let position = util::window_to_tiles(ctx, &main_state.screen_size, x, y);
+    if let MenuInput::Activate(action) = main_state.title.menu.mouse_up(position) {
+        activate(main_state, ctx, action);
+    }
+}
+
+pub fn draw(main_state: &mut MainState, ctx: &mut Context) -> GameResult {
+    let screen_size = main_state.screen_size;
+    let map_size = main_state.game_state.map_size;
+    let title = &main_state.title;
+    util::render_text_row(
+        &title.title_text,
+        ctx,
+        &screen_size,
+        &map_size,
+        1.5,
+        1.5,
+        constantes::COLOR_MENU,
+    )?;
+    title.menu.draw(ctx, &screen_size, &map_size)
+}
@@ -0,0 +1,86 @@
+// Small key/value storage that survives between sessions
+// Native builds write one file per key in the user data dir,
+// the wasm build uses the browser local storage through utils/wasm/storage.js,
+// which every page hosting the wasm has to load (see the README)
+
+const GAME_DIRECTORY: &str = "dig_escape";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> Option<std::path::PathBuf> {
+    use std::env::var_os;
+    use std::path::PathBuf;
+    if cfg!(target_os = "windows") {
+        return var_os("APPDATA").map(PathBuf::from);
+    }
+    if cfg!(target_os = "macos") {
+        return var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"));
+    }
+    var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn key_path(key: &str) -> Option<std::path::PathBuf> {
+    data_dir().map(|dir| dir.join(GAME_DIRECTORY).join(format!("{}.txt", key)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(key_path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+    let path = key_path(key).ok_or("no user data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, value).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn storage_length(key: *const u8, key_len: u32) -> i32;
+    fn storage_read(key: *const u8, key_len: u32, buffer: *mut u8, buffer_len: u32);
+    fn storage_write(key: *const u8, key_len: u32, value: *const u8, value_len: u32);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(key: &str) -> String {
+    format!("{}/{}", GAME_DIRECTORY, key)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+    let key = storage_key(key);
+    // -1 when there is nothing stored under the key
+    let length = unsafe { storage_length(key.as_ptr(), key.len() as u32) };
+    if length < 0 {
+        return None;
+    }
+    let mut buffer = vec![0u8; length as usize];
+    unsafe {
+        storage_read(
+            key.as_ptr(),
+            key.len() as u32,
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        );
+    }
+    String::from_utf8(buffer).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) -> Result<(), String> {
+    let key = storage_key(key);
+    unsafe {
+        storage_write(
+            key.as_ptr(),
+            key.len() as u32,
+            value.as_ptr(),
+            value.len() as u32,
+        );
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use nalgebra as na;
+#[derive(Clone)]
 pub struct TransformComponent {
     pub position: na::Point2<i32>,
 }
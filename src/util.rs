@@ -1,4 +1,5 @@
 use crate::constantes;
+use crate::entities::player::PlayerInputIntent;
 use crate::states::game_state::GameState;
 use ggez::{graphics, Context, GameResult};
 use graphics::DrawParam;
@@ -13,10 +14,11 @@ pub fn render_text(
     text: &graphics::Text,
     ctx: &mut Context,
     screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
     offset: na::Vector2<f32>,
 ) -> GameResult {
-    let size_x = screen_size.x * 10.0;
-    let size_y = screen_size.x * 8.0;
+    let size_x = screen_size.x * map_size.x as f32;
+    let size_y = screen_size.x * map_size.y as f32;
     let mut pos_centered = na::Point2::new(size_x * 0.5, size_y * 0.5);
     let (text_w, text_h) = text.dimensions(ctx);
     let padding_scale = 1.0 - constantes::TEXT_PADDING_SIZE;
@@ -35,6 +37,149 @@ pub fn render_text(
     Ok(())
 }
 
+// Draws text horizontally centered on the map, row and height are in tiles
+pub fn render_text_row(
+    text: &graphics::Text,
+    ctx: &mut Context,
+    screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
+    row: f32,
+    height: f32,
+    color: graphics::Color,
+) -> GameResult {
+    let size_x = screen_size.x * map_size.x as f32;
+    let (text_w, text_h) = text.dimensions(ctx);
+    let padding_scale = 1.0 - constantes::TEXT_PADDING_SIZE;
+    let scale = (height * screen_size.x / text_h).min(size_x * padding_scale / text_w);
+    let pos = na::Point2::new(
+        (size_x - text_w * scale) * 0.5,
+        row * screen_size.x + (height * screen_size.x - text_h * scale) * 0.5,
+    );
+    let draw_param = DrawParam {
+        dest: pos.into(),
+        color,
+        scale: mint::Vector2 { x: scale, y: scale },
+        ..Default::default()
+    };
+    graphics::draw(ctx, text, draw_param)?;
+    Ok(())
+}
+
+// A row of star outlines horizontally centered on the map, the first ones filled.
+// row and height are in tiles
+pub fn render_stars(
+    ctx: &mut Context,
+    screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
+    row: f32,
+    height: f32,
+    filled: u8,
+    count: u8,
+) -> GameResult {
+    let tile = screen_size.x;
+    let radius = height * 0.5 * tile;
+    let spacing = height * 1.2 * tile;
+    let first_x = map_size.x as f32 * tile * 0.5 - spacing * (count as f32 - 1.0) * 0.5;
+    let center_y = row * tile + radius;
+    for index in 0..count {
+        let center = na::Point2::new(first_x + spacing * index as f32, center_y);
+        // 5 outer points pointing up, with inner points between them
+        let points: Vec<na::Point2<f32>> = (0..10)
+            .map(|point| {
+                let angle = point as f32 * std::f32::consts::PI / 5.0 - std::f32::consts::FRAC_PI_2;
+                let distance = if point % 2 == 0 { radius } else { radius * 0.45 };
+                center + na::Vector2::new(angle.cos(), angle.sin()) * distance
+            })
+            .collect();
+        let (mode, color) = match index < filled {
+            true => (graphics::DrawMode::fill(), constantes::COLOR_STAR),
+            false => (graphics::DrawMode::stroke(2.0), constantes::COLOR_MENU_LOCKED),
+        };
+        let mesh = graphics::Mesh::new_polygon(ctx, mode, &points, color)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+    }
+    Ok(())
+}
+
+// Arrow pointing the way of a move, size is the distance from the center to the tip
+pub fn render_arrow(
+    ctx: &mut Context,
+    center: na::Point2<f32>,
+    intent: PlayerInputIntent,
+    size: f32,
+    color: graphics::Color,
+) -> GameResult {
+    let direction = match intent {
+        PlayerInputIntent::Left => na::Vector2::new(-1.0, 0.0),
+        PlayerInputIntent::Right => na::Vector2::new(1.0, 0.0),
+        PlayerInputIntent::Up => na::Vector2::new(0.0, -1.0),
+        PlayerInputIntent::Down => na::Vector2::new(0.0, 1.0),
+        PlayerInputIntent::None => return Ok(()),
+    };
+    let side = na::Vector2::new(-direction.y, direction.x);
+    let points = [
+        center + direction * size,
+        center + side * size * 0.8,
+        center + side * size * 0.3,
+        center + side * size * 0.3 - direction * size,
+        center - side * size * 0.3 - direction * size,
+        center - side * size * 0.3,
+        center - side * size * 0.8,
+    ];
+    let mesh = graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &points, color)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())?;
+    Ok(())
+}
+
+// Darkens the map so text drawn on top of the game is readable
+pub fn render_overlay(
+    ctx: &mut Context,
+    screen_size: &na::Point2<f32>,
+    map_size: &na::Point2<i32>,
+) -> GameResult {
+    let rect = graphics::Rect::new(
+        0.0,
+        0.0,
+        screen_size.x * map_size.x as f32,
+        screen_size.x * map_size.y as f32,
+    );
+    let mesh = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        rect,
+        constantes::COLOR_OVERLAY,
+    )?;
+    graphics::draw(ctx, &mesh, DrawParam::default())?;
+    Ok(())
+}
+
+// Mouse and touch positions are in window pixels, this returns them in map tiles
+pub fn window_to_tiles(ctx: &mut Context, screen_size: &na::Point2<f32>, x: f32, y: f32) -> na::Point2<f32> {
+    let screen_rect = graphics::screen_coordinates(ctx);
+    na::Point2::new(
+        (x + screen_rect.x) / screen_size.x,
+        (y + screen_rect.y) / screen_size.x,
+    )
+}
+
+// Direction of a swipe (mouse or touch), None if it moved too little to count
+pub fn swipe_direction(delta: na::Vector2<f32>) -> PlayerInputIntent {
+    if delta.norm() <= constantes::TOUCH_MIN_DELTA {
+        return PlayerInputIntent::None;
+    }
+    if delta.x.abs() > delta.y.abs() {
+        if delta.x > 0.0 {
+            PlayerInputIntent::Right
+        } else {
+            PlayerInputIntent::Left
+        }
+    } else if delta.y > 0.0 {
+        PlayerInputIntent::Down
+    } else {
+        PlayerInputIntent::Up
+    }
+}
+
 pub fn force_visual_positions(game_state: &mut GameState, screen_size: &na::Point2<f32>) {
     let mut position: na::Point2<f32>;
     for grasses in game_state.grasses.iter_mut() {
@@ -60,6 +205,20 @@ pub fn force_visual_positions(game_state: &mut GameState, screen_size: &na::Poin
     }
 }
 
+// Player and skeletons normally glide to their position, this snaps them there
+pub fn force_actor_visual_positions(game_state: &mut GameState, screen_size: &na::Point2<f32>) {
+    let mut position: na::Point2<f32>;
+    {
+        position =
+            na::convert::<na::Point2<i32>, na::Point2<f32>>(game_state.player.transform.position);
+        game_state.player.sprite.visual_position = position * screen_size.x;
+    }
+    for skeleton in game_state.skeletons.iter_mut() {
+        position = na::convert::<na::Point2<i32>, na::Point2<f32>>(skeleton.transform.position);
+        skeleton.sprite.visual_position = position * screen_size.x;
+    }
+}
+
 pub struct BlackBorder {
     mesh: graphics::Mesh,
     draw_param: graphics::DrawParam,
@@ -0,0 +1,132 @@
+#!/usr/bin/env python3
+# Packs the sprites listed in sprites.txt into resources/textures/atlas.png and writes
+# resources/textures/atlas.txt with where each one ended up.
+# Only needs the python standard library, run it from anywhere after adding or changing a sprite:
+#   python3 utils/atlas/pack_atlas.py
+import os
+import struct
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+SPRITES_DIRECTORY = os.path.join(HERE, "sprites")
+SPRITES_LIST = os.path.join(HERE, "sprites.txt")
+TEXTURES_DIRECTORY = os.path.join(HERE, "..", "..", "resources", "textures")
+
+ATLAS_WIDTH = 128
+PADDING = 2  # empty pixels between sprites, so filtering never bleeds into a neighbour
+
+
+def read_png(path):
+    # 8 bit RGBA, not interlaced, like every sprite in the game
+    data = open(path, "rb").read()
+    position = 8
+    compressed = b""
+    while position < len(data):
+        (length,) = struct.unpack(">I", data[position : position + 4])
+        kind = data[position + 4 : position + 8]
+        body = data[position + 8 : position + 8 + length]
+        position += 12 + length
+        if kind == b"IHDR":
+            width, height, depth, color, _, _, interlace = struct.unpack(">IIBBBBB", body)
+            if depth != 8 or color != 6 or interlace != 0:
+                raise SystemExit("%s: expected an 8 bit RGBA png" % path)
+        elif kind == b"IDAT":
+            compressed += body
+
+    raw = zlib.decompress(compressed)
+    stride = width * 4
+    rows = []
+    previous = bytearray(stride)
+    index = 0
+    for _ in range(height):
+        line_filter = raw[index]
+        line = bytearray(raw[index + 1 : index + 1 + stride])
+        index += 1 + stride
+        for x in range(stride):
+            left = line[x - 4] if x >= 4 else 0
+            up = previous[x]
+            up_left = previous[x - 4] if x >= 4 else 0
+            if line_filter == 1:
+                line[x] = (line[x] + left) & 255
+            elif line_filter == 2:
+                line[x] = (line[x] + up) & 255
+            elif line_filter == 3:
+                line[x] = (line[x] + ((left + up) >> 1)) & 255
+            elif line_filter == 4:
+                estimate = left + up - up_left
+                distances = [abs(estimate - left), abs(estimate - up), abs(estimate - up_left)]
+                predictor = [left, up, up_left][distances.index(min(distances))]
+                line[x] = (line[x] + predictor) & 255
+        rows.append(line)
+        previous = line
+    return width, height, rows
+
+
+def write_png(path, width, height, rows):
+    def chunk(kind, body):
+        checksum = zlib.crc32(kind + body) & 0xFFFFFFFF
+        return struct.pack(">I", len(body)) + kind + body + struct.pack(">I", checksum)
+
+    raw = b"".join(b"\0" + bytes(row) for row in rows)
+    header = struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0)
+    with open(path, "wb") as file:
+        file.write(b"\x89PNG\r\n\x1a\n")
+        file.write(chunk(b"IHDR", header))
+        file.write(chunk(b"IDAT", zlib.compress(raw, 9)))
+        file.write(chunk(b"IEND", b""))
+
+
+def read_sprites_list():
+    sprites = []
+    for line_index, line in enumerate(open(SPRITES_LIST)):
+        words = line.split()
+        if not words or words[0].startswith("#"):
+            continue
+        if len(words) > 2 or not words[0].endswith(".png"):
+            raise SystemExit("sprites.txt line %d: can't read '%s'" % (line_index + 1, line.strip()))
+        group = words[1] if len(words) == 2 else None
+        sprites.append((words[0], group))
+    return sprites
+
+
+# Fills rows left to right in the listed order, a new row starts below the tallest sprite of the last one
+def pack(sprites):
+    placed = []
+    x, y, row_height = 0, 0, 0
+    for file_name, group in sprites:
+        width, height, rows = read_png(os.path.join(SPRITES_DIRECTORY, file_name))
+        if width > ATLAS_WIDTH:
+            raise SystemExit("%s is wider than the atlas" % file_name)
+        if x + width > ATLAS_WIDTH:
+            x, y, row_height = 0, y + row_height + PADDING, 0
+        placed.append((file_name[: -len(".png")], x, y, width, height, group, rows))
+        x += width + PADDING
+        row_height = max(row_height, height)
+
+    # Power of two, some graphics drivers still want it
+    atlas_height = 1
+    while atlas_height < y + row_height:
+        atlas_height *= 2
+    return placed, max(atlas_height, ATLAS_WIDTH)
+
+
+def main():
+    placed, atlas_height = pack(read_sprites_list())
+    atlas = [bytearray(ATLAS_WIDTH * 4) for _ in range(atlas_height)]
+    for _, x, y, width, height, _, rows in placed:
+        for row in range(height):
+            atlas[y + row][x * 4 : (x + width) * 4] = rows[row]
+    write_png(os.path.join(TEXTURES_DIRECTORY, "atlas.png"), ATLAS_WIDTH, atlas_height, atlas)
+
+    with open(os.path.join(TEXTURES_DIRECTORY, "atlas.txt"), "w") as file:
+        file.write("# Sprites packed in atlas.png, one per line: name x y width height [group]\n")
+        file.write("# The game picks sprites of a group at random, adding a line adds a variant\n")
+        file.write("# Made by utils/atlas/pack_atlas.py from utils/atlas/sprites, edit those and run it again\n")
+        for name, x, y, width, height, group, _ in placed:
+            words = [name, x, y, width, height] + ([group] if group else [])
+            file.write(" ".join(str(word) for word in words) + "\n")
+    print("packed %d sprites into a %dx%d atlas" % (len(placed), ATLAS_WIDTH, atlas_height))
+
+
+if __name__ == "__main__":
+    main()
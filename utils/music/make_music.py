@@ -0,0 +1,73 @@
+#!/usr/bin/env python3
+# Writes resources/sounds/music.wav, the looping background track, from the notes below.
+# Only needs the python standard library:
+#   python3 utils/music/make_music.py
+import math
+import os
+import struct
+import wave
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+OUTPUT = os.path.join(HERE, "..", "..", "resources", "sounds", "music.wav")
+
+SAMPLE_RATE = 22050
+BEAT = 0.25  # seconds per step
+VOLUME = 0.3
+
+# One step per entry, semitones from A4 (440 Hz), None is a rest.
+# The loop is 32 steps, every note fades out before the next starts so the loop point doesn't click
+MELODY = [
+    3, None, 7, 10, 8, None, 7, None,
+    5, None, 3, 5, 7, None, None, None,
+    3, None, 7, 10, 12, None, 10, 8,
+    7, None, 5, None, 3, None, None, None,
+]
+BASS = [-21, -21, -14, -14, -16, -16, -9, -9]  # one note per 4 steps
+
+
+def frequency(semitones):
+    return 440.0 * 2.0 ** (semitones / 12.0)
+
+
+# Triangle wave, soft enough to loop for a long time
+def triangle(phase):
+    return 4.0 * abs(phase - math.floor(phase + 0.5)) - 1.0
+
+
+def note(semitones, length, volume):
+    samples = int(length * SAMPLE_RATE)
+    step = frequency(semitones) / SAMPLE_RATE
+    out = []
+    for i in range(samples):
+        # Quick attack, then a decay down to silence at the end of the note
+        envelope = min(1.0, i / 200.0) * (1.0 - i / samples) ** 2
+        out.append(triangle(i * step) * envelope * volume)
+    return out
+
+
+def main():
+    total = int(len(MELODY) * BEAT * SAMPLE_RATE)
+    mix = [0.0] * total
+    step_samples = int(BEAT * SAMPLE_RATE)
+    for index, semitones in enumerate(MELODY):
+        if semitones is None:
+            continue
+        for i, value in enumerate(note(semitones, BEAT * 1.5, 0.6)):
+            mix[(index * step_samples + i) % total] += value
+    for index, semitones in enumerate(BASS):
+        for i, value in enumerate(note(semitones, BEAT * 4, 0.8)):
+            mix[(index * 4 * step_samples + i) % total] += value
+
+    frames = b"".join(
+        struct.pack("<h", int(max(-1.0, min(1.0, value * VOLUME)) * 32767)) for value in mix
+    )
+    with wave.open(OUTPUT, "wb") as file:
+        file.setnchannels(1)
+        file.setsampwidth(2)
+        file.setframerate(SAMPLE_RATE)
+        file.writeframes(frames)
+    print("wrote %s, %.1f seconds" % (os.path.normpath(OUTPUT), total / SAMPLE_RATE))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,37 @@
+// Browser local storage for src/storage.rs, values are utf-8 strings
+register_plugin = function (importObject) {
+    var encoder = new TextEncoder();
+    var decoder = new TextDecoder();
+
+    function read_string(ptr, len) {
+        return decoder.decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+    }
+
+    importObject.env.storage_length = function (key_ptr, key_len) {
+        var value = window.localStorage.getItem(read_string(key_ptr, key_len));
+        if (value == null) {
+            return -1;
+        }
+        return encoder.encode(value).length;
+    }
+
+    importObject.env.storage_read = function (key_ptr, key_len, buffer_ptr, buffer_len) {
+        var value = window.localStorage.getItem(read_string(key_ptr, key_len));
+        if (value == null) {
+            return;
+        }
+        var bytes = encoder.encode(value).subarray(0, buffer_len);
+        new Uint8Array(wasm_memory.buffer, buffer_ptr, buffer_len).set(bytes);
+    }
+
+    importObject.env.storage_write = function (key_ptr, key_len, value_ptr, value_len) {
+        try {
+            window.localStorage.setItem(read_string(key_ptr, key_len), read_string(value_ptr, value_len));
+        } catch (e) {
+            // private browsing or storage full, progress just won't be saved
+            console.log(e);
+        }
+    }
+}
+
+miniquad_add_plugin({ register_plugin });